@@ -1,16 +1,174 @@
+use std::{f32::consts::FRAC_PI_2, fmt, str::FromStr};
+
 use derive_more::Constructor;
-use nalgebra::{Matrix4, Vector3};
-use crate::{EPSILON, objects::{Line3f, Point3f, Primitive3f}};
+use nalgebra::{Isometry3, Matrix4, Rotation3, Translation3, Unit, UnitQuaternion, Vector3};
+
+use crate::{DEFAULT_FAR, EPSILON, frustum::Frustum, objects::{Line3f, Point3f, Primitive3f, Triangle3f, bounds::BoundingSphere}};
 
+//pose de la camera : position et rotation unitaire (quaternion) de son repère vers le monde
+//la rotation est renormalisée après chaque composition et world_to_cam en est déduite par la rotation inverse, sans inversion de matrice
 pub struct Camera {
     pub perspective_center_distance: f32,
+    far: f32, //distance du plan lointain, le plan proche est à EPSILON en perspective (voir Projector::near)
+    position: Vector3<f32>,
+    orientation: UnitQuaternion<f32>,
+    up_axis: Option<UpAxis>, //si présent : lacet autour de cet axe du monde, pas de roulis, tangage limité
+    projection: Projection,
+    ortho_blend: f32, //0 en perspective, 1 en orthographique, entre les deux pendant le passage de l'une à l'autre
+    focus_distance: f32, //distance du plan dont l'échelle ne dépend pas de la projection, la demi-hauteur vue en orthographique en est déduite
     world_to_cam: Matrix4<f32>,
-    cam_to_world: Matrix4<f32>,
+}
+
+//projection de l'image : perspective, ou orthographique (parallèle) pour lire les formes sans déformation
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    Orthographic,
+}
+
+impl Projection {
+    pub fn next(self) -> Self {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        }
+    }
+
+    fn blend(self) -> f32 {
+        match self {
+            Projection::Perspective => 0.0,
+            Projection::Orthographic => 1.0,
+        }
+    }
+}
+
+impl FromStr for Projection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "perspective" => Ok(Projection::Perspective),
+            "ortho" => Ok(Projection::Orthographic),
+            other => Err(anyhow::anyhow!("Projection '{}' inconnue (perspective ou ortho)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Projection::Perspective => "perspective",
+            Projection::Orthographic => "ortho",
+        };
+        write!(f, "{name}")
+    }
+}
+
+//projection d'un point du repère de la camera sur l'image : (x, y) f / w(z), avec w(z) = (1 - blend) z + blend focus
+//blend = 0 donne la perspective (f x / z), blend = 1 l'orthographique (f x / focus, la demi-hauteur vue est focus / f)
+//entre les deux, c'est une perspective dont le centre recule en même temps que le champ se resserre : l'échelle du plan z = focus ne change pas
+#[derive(Clone, Copy, Debug)]
+pub struct Projector {
+    pub f: f32,
+    pub blend: f32,
+    pub focus: f32,
+    pub far: f32,
+}
+
+impl Projector {
+    //diviseur de la projection, strictement positif entre les plans proche et lointain
+    pub fn w(&self, z: f32) -> f32 {
+        (1.0 - self.blend) * z + self.blend * self.focus
+    }
+
+    //plan proche : EPSILON devant la camera en perspective, reculé jusqu'à focus derrière elle en orthographique
+    pub fn near(&self) -> f32 {
+        EPSILON - self.blend * self.focus
+    }
+
+    //valeur écrite dans le z-buffer : croissante avec z, et d'inverse linéaire dans l'espace écran comme l'attendent draw_line et draw_triangle
+    //c'est z en perspective, en orthographique où w est constant on ne peut plus interpoler 1/z
+    pub fn depth(&self, z: f32) -> f32 {
+        self.w(z) / (1.0 - self.blend * z / (self.far + self.focus))
+    }
+
+    pub fn project(&self, p: &Vector3<f32>) -> (f32, f32) {
+        let w = self.w(p.z);
+        (self.f * p.x / w, self.f * p.y / w)
+    }
+}
+
+//axe du monde gardé vertical à l'écran par une camera contrainte
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UpAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl UpAxis {
+    pub fn vector(self) -> Vector3<f32> {
+        match self {
+            UpAxis::X => Vector3::x(),
+            UpAxis::Y => Vector3::y(),
+            UpAxis::Z => Vector3::z(),
+        }
+    }
+}
+
+impl FromStr for UpAxis {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "x" => Ok(UpAxis::X),
+            "y" => Ok(UpAxis::Y),
+            "z" => Ok(UpAxis::Z),
+            other => Err(anyhow::anyhow!("Axe '{}' inconnu (x, y ou z)", other)),
+        }
+    }
+}
+
+impl fmt::Display for UpAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            UpAxis::X => "x",
+            UpAxis::Y => "y",
+            UpAxis::Z => "z",
+        };
+        write!(f, "{name}")
+    }
+}
+
+//une camera contrainte ne regarde jamais exactement selon l'axe vertical, où sa droite ne serait plus définie
+const MAX_ELEVATION: f32 = FRAC_PI_2 - 0.01;
+
+//marge sur MAX_ELEVATION : un vecteur déjà ramené à la limite, que les arrondis ont fait passer un peu au-delà, n'est pas ramené à nouveau
+const ELEVATION_TOLERANCE: f32 = 1e-4;
+
+//v dont l'angle avec le plan horizontal (normal à up) est ramené dans [-MAX_ELEVATION; MAX_ELEVATION], même direction horizontale et même norme
+//fallback donne la direction horizontale quand v est vertical
+fn clamp_elevation(v: Vector3<f32>, up: &Vector3<f32>, fallback: Vector3<f32>) -> Vector3<f32> {
+    let norm = v.norm();
+    let elevation = (v.dot(up) / norm).clamp(-1.0, 1.0).asin();
+    if elevation.abs() <= MAX_ELEVATION + ELEVATION_TOLERANCE {
+        return v;
+    }
+
+    let horizontal = (v - v.dot(up) * up)
+        .try_normalize(1e-6)
+        .or_else(|| (fallback - fallback.dot(up) * up).try_normalize(1e-6));
+    match horizontal {
+        Some(horizontal) => norm * (MAX_ELEVATION.cos() * horizontal + elevation.signum() * MAX_ELEVATION.sin() * up),
+        None => v,
+    }
 }
 
 pub enum Primitive2f {
     Line(Line2f),
     Point(Point2f),
+    Triangle(Triangle2f),
 }
 
 #[derive(Constructor)]
@@ -19,24 +177,38 @@ pub struct Line2f {
     pub p1: Point2f,
 }
 
+#[derive(Constructor)]
+pub struct Triangle2f {
+    pub p0: Point2f,
+    pub p1: Point2f,
+    pub p2: Point2f,
+}
+
 #[derive(Constructor)]
 pub struct Point2f {
     pub p: (f32, f32),
+    pub depth: f32, //profondeur pour le z-buffer, z dans le repère de la caméra en perspective (voir Projector::depth)
     pub color: u32,
 }
 
 impl Camera {
     pub fn new_at_origin(fovy: f32) -> Self {
-        let cam_to_world = Matrix4::identity();
-        let world_to_cam = Matrix4::identity();
-
         let perspective_center_distance = 1.0 / (fovy / 2.0).tan();
 
-        Self {
-            world_to_cam,
-            cam_to_world,
+        let mut camera = Self {
             perspective_center_distance,
-        }
+            far: DEFAULT_FAR,
+            position: Vector3::zeros(),
+            orientation: UnitQuaternion::identity(),
+            up_axis: None,
+            projection: Projection::Perspective,
+            ortho_blend: 0.0,
+            focus_distance: 1.0,
+            world_to_cam: Matrix4::identity(),
+        };
+        camera.update_world_to_cam();
+
+        camera
     }
 
     pub fn new_looking_at_origin_from(fovy: f32, init_droll: f32, init_dpitch: f32, init_dyaw: f32, distance: f32) -> Self {
@@ -46,153 +218,272 @@ impl Camera {
         camera.rotate_pitch(init_dpitch);
         camera.rotate_yaw(init_dyaw);
         camera.translate_relative(Vector3::new(0.0, 0.0, -distance));
+        camera.set_focus_distance(distance);
+
+        camera
+    }
+
+    //restaure une pose complète (par exemple lue dans un fichier de scène), la partie linéaire est ramenée à la rotation la plus proche
+    //le plan de mise au point passe par l'origine, ou est à une unité devant la camera si l'origine est derrière elle
+    pub fn new_from_pose(fovy: f32, cam_to_world: Matrix4<f32>) -> Self {
+        let mut camera = Self::new_at_origin(fovy);
+        camera.position = cam_to_world.fixed_view::<3, 1>(0, 3).into_owned();
+        camera.orientation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(cam_to_world.fixed_view::<3, 3>(0, 0).into_owned()));
+        camera.update_world_to_cam();
+        camera.focus_distance = (-camera.position).dot(&camera.forward()).max(1.0);
 
         camera
     }
 
+    pub fn fovy(&self) -> f32 {
+        2.0 * (1.0 / self.perspective_center_distance).atan()
+    }
+
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    pub fn set_far(&mut self, far: f32) {
+        assert!(far > EPSILON, "Le plan lointain doit être au-delà du plan proche ({EPSILON})");
+        self.far = far;
+    }
+
+    pub fn up_axis(&self) -> Option<UpAxis> {
+        self.up_axis
+    }
+
+    //avec un axe, la camera est aussitôt redressée : son roulis est annulé et son tangage limité
+    pub fn set_up_axis(&mut self, up_axis: Option<UpAxis>) {
+        self.up_axis = up_axis;
+        self.update_world_to_cam();
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    //change de projection immédiatement
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.ortho_blend = projection.blend();
+    }
+
+    //passe à l'autre projection, progressivement au fil des appels à blend_projection
+    pub fn switch_projection(&mut self) {
+        self.projection = self.projection.next();
+    }
+
+    //avance le passage vers la projection choisie de step (1 pour le passage complet), retourne vrai si l'image change
+    pub fn blend_projection(&mut self, step: f32) -> bool {
+        let target = self.projection.blend();
+        if self.ortho_blend == target {
+            return false;
+        }
+
+        self.ortho_blend = match self.ortho_blend < target {
+            true => (self.ortho_blend + step).min(target),
+            false => (self.ortho_blend - step).max(target),
+        };
+        true
+    }
+
+    pub fn focus_distance(&self) -> f32 {
+        self.focus_distance
+    }
+
+    //typiquement la distance à la cible : un objet qui y est cadré garde sa taille en changeant de projection, et en orthographique s'en rapprocher l'agrandit
+    pub fn set_focus_distance(&mut self, distance: f32) {
+        if distance.is_finite() {
+            self.focus_distance = distance.max(EPSILON);
+        }
+    }
+
+    pub fn projector(&self) -> Projector {
+        Projector { f: self.perspective_center_distance, blend: self.ortho_blend, focus: self.focus_distance, far: self.far }
+    }
+
+    //volume de vision dans le repère de la camera, pour une image de rapport aspect = largeur / hauteur
+    pub fn frustum(&self, aspect: f32) -> Frustum {
+        Frustum::new(&self.projector(), aspect)
+    }
+
+    fn isometry(&self) -> Isometry3<f32> {
+        Isometry3::from_parts(Translation3::from(self.position), self.orientation)
+    }
+
+    pub fn cam_to_world(&self) -> Matrix4<f32> {
+        self.isometry().to_homogeneous()
+    }
+
+    pub fn world_to_cam(&self) -> Matrix4<f32> {
+        self.world_to_cam
+    }
+
+    pub fn orientation(&self) -> UnitQuaternion<f32> {
+        self.orientation
+    }
+
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    //axes du repère de la camera exprimés dans le monde : x à droite, y en haut, z devant
+    pub fn right(&self) -> Vector3<f32> {
+        self.orientation * Vector3::x()
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.orientation * Vector3::y()
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        self.orientation * Vector3::z()
+    }
+
+    //à appeler après chaque changement de pose : renormalise la rotation, applique la contrainte d'axe vertical puis déduit world_to_cam
     fn update_world_to_cam(&mut self) {
-        self.world_to_cam = self.cam_to_world.try_inverse().unwrap();
+        self.orientation.renormalize();
+        self.level();
+        self.world_to_cam = self.isometry().inverse().to_homogeneous();
+    }
+
+    //avec un axe vertical, reconstruit la rotation à partir de la visée : la droite est horizontale et le regard reste à moins de MAX_ELEVATION de l'horizontale
+    fn level(&mut self) {
+        let Some(up_axis) = self.up_axis else { return };
+        let up = up_axis.vector();
+
+        let forward = clamp_elevation(self.forward(), &up, -self.up());
+        //déjà redressée aux arrondis près : la rotation est gardée telle quelle, sans quoi une pose relue puis réécrite dériverait
+        if forward == self.forward() && self.right().dot(&up).abs() <= ELEVATION_TOLERANCE && self.up().dot(&up) > 0.0 {
+            return;
+        }
+
+        let forward = forward.normalize();
+        if let Some(right) = up.cross(&forward).try_normalize(1e-6) {
+            let rotation = Rotation3::from_basis_unchecked(&[right, forward.cross(&right), forward]);
+            self.orientation = UnitQuaternion::from_rotation_matrix(&rotation);
+        }
     }
 
     //effectue une translation de la caméra dans le monde, selon un vecteur exprimé dans le repère du monde
     pub fn translate_absolute(&mut self, dp: Vector3<f32>) {
-        let translation_matrix = Matrix4::new(
-            1.0, 0.0, 0.0, dp.x,
-            0.0, 1.0, 0.0, dp.y,
-            0.0, 0.0, 1.0, dp.z,
-            0.0, 0.0, 0.0, 1.0
-        );
-
-        self.cam_to_world = translation_matrix * self.cam_to_world;
+        self.position += dp;
         self.update_world_to_cam();
     }
 
     //effectue une translation de la camera dans le monde, selon un vecteur exprimé dans le repère de la caméra
     pub fn translate_relative(&mut self, dp: Vector3<f32>) {
-        let translation_matrix = Matrix4::new(
-            1.0, 0.0, 0.0, dp.x,
-            0.0, 1.0, 0.0, dp.y,
-            0.0, 0.0, 1.0, dp.z,
-            0.0, 0.0, 0.0, 1.0
-        );
-
-        self.cam_to_world = self.cam_to_world * translation_matrix;
+        self.position += self.orientation * dp;
+        self.update_world_to_cam();
+    }
+
+    //rotation de la camera autour d'un de ses propres axes
+    fn rotate_local(&mut self, axis: Unit<Vector3<f32>>, angle: f32) {
+        self.orientation *= UnitQuaternion::from_axis_angle(&axis, angle);
         self.update_world_to_cam();
     }
 
-    //rotation de la camera sur son vecteur "devant" (axe z de son repère)
+    //rotation de la camera sur son vecteur "devant" (axe z de son repère), sans effet pour une camera contrainte
     pub fn rotate_roll(&mut self, d_phi: f32) {
-         let cos_phi = d_phi.cos();
-         let sin_phi = d_phi.sin();
+        if self.up_axis.is_none() {
+            self.rotate_local(Vector3::z_axis(), d_phi);
+        }
+    }
 
-         let rotation_matrix = Matrix4::new(
-            cos_phi, -sin_phi, 0.0, 0.0,
-            sin_phi, cos_phi,  0.0, 0.0,
-            0.0,     0.0,      1.0, 0.0,
-            0.0,     0.0,      0.0, 1.0,
-         );
+    //rotation de la camera sur son vecteur "droite" (axe x de son repère), un angle positif abaisse le regard
+    pub fn rotate_pitch(&mut self, d_theta: f32) {
+        self.rotate_local(Vector3::x_axis(), d_theta);
+    }
 
-         self.cam_to_world = self.cam_to_world * rotation_matrix;
-         self.update_world_to_cam();
+    //rotation de la camera sur son vecteur "haut" (axe y de son repère), un angle positif tourne vers la gauche
+    //pour une camera contrainte, la rotation se fait autour de l'axe vertical du monde
+    pub fn rotate_yaw(&mut self, d_psi: f32) {
+        match self.up_axis {
+            Some(up_axis) => {
+                self.orientation = UnitQuaternion::from_axis_angle(&Unit::new_unchecked(up_axis.vector()), -d_psi) * self.orientation;
+                self.update_world_to_cam();
+            }
+            None => self.rotate_local(Vector3::y_axis(), -d_psi),
+        }
     }
 
-    //rotation de la camera sur son vecteur "droite" (axe x de son repère)
-    pub fn rotate_pitch(&mut self, d_theta: f32) {
-        let cos_theta = d_theta.cos();
-        let sin_theta = d_theta.sin();
+    //fait tourner la camera autour d'un point du monde, la rotation est exprimée dans le repère du monde
+    //une camera contrainte ne passe pas au dessus du point : sa position est limitée comme sa visée
+    pub fn orbit(&mut self, center: &Vector3<f32>, rotation: &Rotation3<f32>) {
+        let rotation = UnitQuaternion::from_rotation_matrix(rotation);
+        let mut offset = rotation * (self.position - center);
+        self.orientation = rotation * self.orientation;
 
-        let rotation_matrix = Matrix4::new(
-            1.0, 0.0, 0.0, 0.0,
-            0.0, cos_theta, -sin_theta, 0.0,
-            0.0, sin_theta, cos_theta, 0.0,
-            0.0, 0.0, 0.0, 1.0,
-        );
+        if let Some(up_axis) = self.up_axis {
+            offset = clamp_elevation(offset, &up_axis.vector(), self.up());
+        }
 
-        self.cam_to_world = self.cam_to_world * rotation_matrix;
+        self.position = center + offset;
         self.update_world_to_cam();
     }
 
-    //rotation de la camera sur son vecteur "haut" (axe y de son repère)
-    pub fn rotate_yaw(&mut self, d_psi: f32) {
-        let cos_psi = d_psi.cos();
-        let sin_psi = d_psi.sin();
+    //recule la camera sur son axe de visée, sans la tourner, pour que la sphère remplisse le champ de vision (aspect = largeur / hauteur)
+    //le plan lointain est repoussé si besoin pour que la sphère ne soit pas coupée, la mise au point se fait sur son centre
+    //la distance en perspective suffit aussi en orthographique, où la demi-hauteur vue est distance / f
+    //retourne le centre de la sphère, la nouvelle cible de la camera
+    pub fn frame(&mut self, sphere: &BoundingSphere, aspect: f32) -> Vector3<f32> {
+        const MARGIN: f32 = 1.05;
 
-        let rotation_matrix = Matrix4::new(
-            cos_psi, 0.0, -sin_psi, 0.0,
-            0.0,     1.0, 0.0,      0.0,
-            sin_psi, 0.0, cos_psi,  0.0,
-            0.0,     0.0, 0.0,      1.0
-        );
+        let half_fovy = (1.0 / self.perspective_center_distance).atan();
+        let half_fovx = (aspect / self.perspective_center_distance).atan();
+        let distance = (MARGIN * sphere.radius / half_fovy.min(half_fovx).sin()).max(2.0 * EPSILON);
 
-        self.cam_to_world = self.cam_to_world * rotation_matrix;
-        self.update_world_to_cam();
+        let translation = sphere.center - distance * self.forward() - self.position();
+        self.translate_absolute(translation);
+        self.far = self.far.max(distance + 2.0 * sphere.radius);
+        self.set_focus_distance(distance);
+
+        sphere.center
     }
 
     pub fn world_line_to_camera_coordinates(&self, line: &Line3f) -> Line3f {
         Line3f::new(self.world_point_to_camera_coordinates(&line.p0), self.world_point_to_camera_coordinates(&line.p1))
     }
 
-    fn world_point_to_camera_coordinates(&self, point: &Point3f) -> Point3f {
-        let mut homogeneous = point.p.to_homogeneous();
-        homogeneous.w = 1.0;
-        let from_cam = self.world_to_cam * homogeneous;
+    pub fn world_to_camera_coordinates(&self, p: &Vector3<f32>) -> Vector3<f32> {
+        self.world_to_cam.transform_point(&(*p).into()).coords
+    }
 
-        Point3f::new(Vector3::new(from_cam.x, from_cam.y, from_cam.z), point.color)
+    fn world_point_to_camera_coordinates(&self, point: &Point3f) -> Point3f {
+        Point3f::new(self.world_to_camera_coordinates(&point.p), point.color)
     }
 
     pub fn world_primitive_to_camera_coordinates(&self, primitive: &Primitive3f) -> Primitive3f {
         match primitive {
             Primitive3f::Line(line) => Primitive3f::Line(self.world_line_to_camera_coordinates(line)),
             Primitive3f::Point(point) => Primitive3f::Point(self.world_point_to_camera_coordinates(point)),
+            Primitive3f::Triangle(triangle) => Primitive3f::Triangle(Triangle3f::new(
+                self.world_point_to_camera_coordinates(&triangle.p0),
+                self.world_point_to_camera_coordinates(&triangle.p1),
+                self.world_point_to_camera_coordinates(&triangle.p2),
+                triangle.normals,
+            )),
         }
     }
 }
 
-pub fn project_primitive(primitive: Primitive3f, perspective_center_distance: f32) -> Primitive2f {
+pub fn project_primitive(primitive: Primitive3f, projector: &Projector) -> Primitive2f {
     match primitive {
-        Primitive3f::Line(line) => Primitive2f::Line(project_line(line, perspective_center_distance)),
-        Primitive3f::Point(point) => Primitive2f::Point(project_point(point, perspective_center_distance)),
+        Primitive3f::Line(line) => Primitive2f::Line(project_line(line, projector)),
+        Primitive3f::Point(point) => Primitive2f::Point(project_point(point, projector)),
+        Primitive3f::Triangle(triangle) => Primitive2f::Triangle(Triangle2f::new(
+            project_point(triangle.p0, projector),
+            project_point(triangle.p1, projector),
+            project_point(triangle.p2, projector),
+        )),
     }
 }
 
-//fait l'hypothèse que le segment et projetable dans son intégralité (devant le plan xy) et exprimé dans le repère de la caméra
-pub fn project_line(line: Line3f, perspective_center_distance: f32) -> Line2f {
-    Line2f::new(project_point(line.p0, perspective_center_distance), project_point(line.p1, perspective_center_distance))
-}
-
-fn project_point(point: Point3f, f: f32) -> Point2f {
-    let x_proj = f * point.p.x / point.p.z;
-    let y_proj = f * point.p.y / point.p.z;
-    Point2f::new((x_proj, y_proj), point.color)
+//fait l'hypothèse que le segment a été découpé par le volume de vision (voir Frustum) et est exprimé dans le repère de la caméra
+pub fn project_line(line: Line3f, projector: &Projector) -> Line2f {
+    Line2f::new(project_point(line.p0, projector), project_point(line.p1, projector))
 }
 
-pub fn filter_primitive_3d(primitive: Primitive3f) -> Option<Primitive3f> {
-    match primitive {
-        Primitive3f::Line(line) => filter_line_3d(line).map(Primitive3f::Line),
-        Primitive3f::Point(point) => if point.p.z > EPSILON { Some(Primitive3f::Point(point)) } else { None }
-    }
-}
-
-//pour supprimer les segments situés derrière le plan de la camera (si un sengement coupe le plan, l'adapte pour qu'il soit projetable)
-pub fn filter_line_3d(line: Line3f) -> Option<Line3f> {
-    match (line.p0.p.z > 0.0, line.p1.p.z > 0.0) {
-        (true, true) => Some(line),
-        (true, false) => Some(adjusted_line_3d(line)),
-        (false, true) => Some(adjusted_line_3d(line.inverted())),
-        (false, false) => None,
-    }
+fn project_point(point: Point3f, projector: &Projector) -> Point2f {
+    Point2f::new(projector.project(&point.p), projector.depth(point.p.z), point.color)
 }
-
-//pour un segment donc p0 est situé devant le plan xy (z >= 0) et p1 derriere, tranlate p1 sur le segment de manière à le positionner sur xy
-fn adjusted_line_3d(mut line: Line3f) -> Line3f {
-    let dxdz = (line.p1.p.x - line.p0.p.x) / (line.p1.p.z - line.p0.p.z);
-    let dydz = (line.p1.p.y - line.p0.p.y) / (line.p1.p.z - line.p0.p.z);
-
-    let dx_intersection = dxdz * (-line.p0.p.z + EPSILON);
-    let dy_intersection = dydz * (-line.p0.p.z + EPSILON);
-
-    let p1 = Vector3::new(line.p0.p.x + dx_intersection, line.p0.p.y + dy_intersection, EPSILON);
-    line.p1.p = p1;
-
-    line
-}
\ No newline at end of file
@@ -0,0 +1,143 @@
+use std::{fmt, str::FromStr};
+
+use nalgebra::{Rotation3, Unit, Vector3};
+
+use crate::{EPSILON, camera::Camera};
+
+//façon dont la fenêtre interactive déplace la camera
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CameraMode {
+    #[default]
+    Orbit, //rotation autour d'une cible à la souris (arcball), déplacement latéral et molette pour avancer
+    FirstPerson, //déplacements et rotations sur les axes de la camera au clavier
+}
+
+impl CameraMode {
+    pub fn next(self) -> Self {
+        match self {
+            CameraMode::Orbit => CameraMode::FirstPerson,
+            CameraMode::FirstPerson => CameraMode::Orbit,
+        }
+    }
+}
+
+impl FromStr for CameraMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "orbit" => Ok(CameraMode::Orbit),
+            "fps" => Ok(CameraMode::FirstPerson),
+            other => Err(anyhow::anyhow!("Mode de camera '{}' inconnu (orbit ou fps)", other)),
+        }
+    }
+}
+
+impl fmt::Display for CameraMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CameraMode::Orbit => "orbit",
+            CameraMode::FirstPerson => "fps",
+        };
+        write!(f, "{name}")
+    }
+}
+
+//vitesses des déplacements au clavier, par seconde : elles sont multipliées par la durée réelle de chaque image
+pub const MOVE_SPEED: f32 = 0.75; //en rayons de la scène visible
+pub const LOOK_SPEED: f32 = 0.6; //radians, rotation de la camera sur elle-même
+pub const TURN_SPEED: f32 = 1.2; //radians, rotation autour de la cible
+pub const DOLLY_SPEED: f32 = 5.0; //pas de 10 % vers la cible
+pub const PAN_SPEED: f32 = 250.0; //pixels
+pub const TURNTABLE_SPEED: f32 = 0.5; //radians, rotation automatique autour de la cible
+pub const PROJECTION_SWITCH_TIME: f32 = 0.4; //secondes, passage d'une projection à l'autre
+pub const MAX_FRAME_TIME: f32 = 0.1; //au delà (image très lente, fenêtre déplacée), un pas plus long ferait sauter la camera
+
+//multiplicateur des vitesses : Maj accélère, Ctrl ralentit, les deux ensemble s'annulent
+pub fn speed_factor(fast: bool, slow: bool) -> f32 {
+    match (fast, slow) {
+        (true, false) => 4.0,
+        (false, true) => 0.25,
+        _ => 1.0,
+    }
+}
+
+//camera en orbite autour d'une cible, les positions de la souris sont en pixels dans une fenêtre width x height
+pub struct OrbitControls {
+    pub target: Vector3<f32>,
+}
+
+impl OrbitControls {
+    pub fn new(target: Vector3<f32>) -> Self {
+        Self { target }
+    }
+
+    //cible sur l'axe de visée, au point le plus proche de point (et au moins une unité devant la camera)
+    pub fn new_facing(camera: &Camera, point: &Vector3<f32>) -> Self {
+        let distance = (point - camera.position()).dot(&camera.forward()).max(1.0);
+        Self::new(camera.position() + distance * camera.forward())
+    }
+
+    pub fn distance(&self, camera: &Camera) -> f32 {
+        (self.target - camera.position()).norm()
+    }
+
+    //replace la cible devant la camera à la même distance, après qu'elle a été déplacée autrement
+    pub fn refocus(&mut self, camera: &Camera) {
+        self.target = camera.position() + self.distance(camera).max(1.0) * camera.forward();
+    }
+
+    //rotation autour de la cible selon les axes "haut" (vertical du monde pour une camera contrainte) puis "droite" de la camera
+    pub fn turn(&self, camera: &mut Camera, yaw: f32, pitch: f32) {
+        let up = camera.up_axis().map_or_else(|| camera.up(), |axis| axis.vector());
+        let rotation = Rotation3::from_axis_angle(&Unit::new_normalize(up), yaw) * Rotation3::from_axis_angle(&Unit::new_normalize(camera.right()), pitch);
+        camera.orbit(&self.target, &rotation);
+    }
+
+    //rotation autour de l'axe vertical du monde (celui de la camera contrainte, z sinon) passant par la cible, pour faire tourner la scène comme sur un plateau
+    pub fn spin(&self, camera: &mut Camera, angle: f32) {
+        let up = camera.up_axis().map_or(Vector3::z_axis(), |axis| Unit::new_unchecked(axis.vector()));
+        camera.orbit(&self.target, &Rotation3::from_axis_angle(&up, angle));
+    }
+
+    //arcball : le point de la sphère virtuelle sous la souris suit le curseur, la camera tourne donc dans le sens inverse
+    pub fn drag_rotate(&self, camera: &mut Camera, from: (f32, f32), to: (f32, f32), width: usize, height: usize) {
+        let (v0, v1) = (arcball_vector(from, width, height), arcball_vector(to, width, height));
+        let Some(rotation) = Rotation3::rotation_between(&v0, &v1) else { return };
+
+        let cam_rotation = Rotation3::from_basis_unchecked(&[camera.right(), camera.up(), camera.forward()]);
+        camera.orbit(&self.target, &(cam_rotation * rotation.inverse() * cam_rotation.inverse()));
+    }
+
+    //déplacement dans le plan de l'écran, la cible reste sous le curseur
+    pub fn pan(&mut self, camera: &mut Camera, from: (f32, f32), to: (f32, f32), height: usize) {
+        let world_per_pixel = 2.0 * self.distance(camera) / (camera.perspective_center_distance * height as f32);
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let translation = world_per_pixel * (-dx * camera.right() + dy * camera.up());
+
+        camera.translate_absolute(translation);
+        self.target += translation;
+    }
+
+    //rapproche (steps > 0) ou éloigne la camera de la cible, de 10 % par pas
+    pub fn dolly(&self, camera: &mut Camera, steps: f32) {
+        let distance = self.distance(camera);
+        let new_distance = (distance * 0.9f32.powf(steps)).max(EPSILON);
+
+        camera.translate_relative(Vector3::new(0.0, 0.0, distance - new_distance));
+    }
+}
+
+//point de la sphère unité centrée sur la fenêtre, dans le repère de la camera (face visible vers -z)
+//hors de la sphère, le point est ramené sur son bord
+fn arcball_vector((x, y): (f32, f32), width: usize, height: usize) -> Vector3<f32> {
+    let radius = width.min(height) as f32 / 2.0;
+    let x = (x - width as f32 / 2.0) / radius;
+    let y = (height as f32 / 2.0 - y) / radius;
+    let r2 = x * x + y * y;
+
+    match r2 <= 1.0 {
+        true => Vector3::new(x, y, -(1.0 - r2).sqrt()),
+        false => Vector3::new(x, y, 0.0) / r2.sqrt(),
+    }
+}
@@ -0,0 +1,159 @@
+use std::{fmt, str::FromStr};
+
+use derive_more::Constructor;
+
+use crate::{drawing::{FrameBuffer, text::{GLYPH_SIZE, TEXT_COLOR, darken, draw_text, text_width}}, objects::lerp_color};
+
+//palettes pour représenter un champ scalaire, échantillonnées régulièrement et interpolées linéairement
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Plasma,
+    Coolwarm,
+    Diverging, //bleu - blanc - rouge, l'intervalle automatique est centré en zéro
+}
+
+const VIRIDIS: [u32; 10] = [0x440154, 0x482878, 0x3e4989, 0x31688e, 0x26828e, 0x1f9e89, 0x35b779, 0x6ece58, 0xb5de2b, 0xfde725];
+const PLASMA: [u32; 10] = [0x0d0887, 0x46039f, 0x7201a8, 0x9c179e, 0xbd3786, 0xd8576b, 0xed7953, 0xfb9f3a, 0xfdca26, 0xf0f921];
+const COOLWARM: [u32; 8] = [0x3b4cc0, 0x6788ee, 0x9abbff, 0xc9d7f0, 0xedd1c2, 0xf7a889, 0xe26952, 0xb40426];
+const DIVERGING: [u32; 9] = [0x2166ac, 0x4393c3, 0x92c5de, 0xd1e5f0, 0xf7f7f7, 0xfddbc7, 0xf4a582, 0xd6604d, 0xb2182b];
+
+//couleur des points où le champ n'est pas défini (paramètrage dégénéré)
+pub const UNDEFINED_COLOR: u32 = 0x808080;
+
+impl Colormap {
+    fn samples(&self) -> &'static [u32] {
+        match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Plasma => &PLASMA,
+            Colormap::Coolwarm => &COOLWARM,
+            Colormap::Diverging => &DIVERGING,
+        }
+    }
+
+    pub fn is_diverging(&self) -> bool {
+        *self == Colormap::Diverging
+    }
+
+    //couleur en t dans [0; 1]
+    pub fn color(&self, t: f32) -> u32 {
+        let samples = self.samples();
+        let x = t.clamp(0.0, 1.0) * (samples.len() - 1) as f32;
+        let i = (x.floor() as usize).min(samples.len() - 2);
+
+        lerp_color(samples[i], samples[i + 1], x - i as f32)
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "viridis" => Ok(Colormap::Viridis),
+            "plasma" => Ok(Colormap::Plasma),
+            "coolwarm" => Ok(Colormap::Coolwarm),
+            "diverging" => Ok(Colormap::Diverging),
+            other => Err(anyhow::anyhow!("Palette '{}' inconnue (viridis, plasma, coolwarm ou diverging)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Colormap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Colormap::Viridis => "viridis",
+            Colormap::Plasma => "plasma",
+            Colormap::Coolwarm => "coolwarm",
+            Colormap::Diverging => "diverging",
+        };
+        write!(f, "{name}")
+    }
+}
+
+//palette associée à l'intervalle de valeurs qu'elle représente
+#[derive(Constructor, Clone, Debug, PartialEq)]
+pub struct Legend {
+    pub label: String,
+    pub colormap: Colormap,
+    pub range: (f32, f32),
+}
+
+impl Legend {
+    pub fn color(&self, value: f32) -> u32 {
+        if !value.is_finite() {
+            return UNDEFINED_COLOR;
+        }
+
+        let (min, max) = self.range;
+        let t = if max > min { (value - min) / (max - min) } else { 0.5 };
+        self.colormap.color(t)
+    }
+}
+
+//barre verticale en bas à droite de l'écran, max en haut, par dessus la scène (sans test de profondeur)
+//des graduations marquent les quarts de l'intervalle, et zéro s'il en fait partie, avec leur valeur à gauche
+//le nom du champ est écrit au dessus de la barre
+pub fn draw_colorbar(frame: &mut FrameBuffer, legend: &Legend) {
+    const BAR_WIDTH: usize = 16;
+    const TICK_LENGTH: usize = 6;
+    const MARGIN: usize = 20;
+    const GAP: usize = 3;
+
+    let (min, max) = legend.range;
+    let mut ticks: Vec<(f32, f32)> = [0.0, 0.25, 0.5, 0.75, 1.0].into_iter().map(|t| (t, min + t * (max - min))).collect();
+    if min < 0.0 && 0.0 < max {
+        ticks.push((-min / (max - min), 0.0));
+    }
+
+    let labels: Vec<String> = ticks.iter().map(|&(_, value)| tick_label(value)).collect();
+    let labels_width = labels.iter().map(|label| text_width(label)).max().unwrap_or(0);
+
+    let height = (frame.height / 3).max(2);
+    if frame.width < BAR_WIDTH + TICK_LENGTH + GAP + labels_width + MARGIN || frame.height < height + MARGIN + 2 * GLYPH_SIZE + GAP {
+        return;
+    }
+
+    let x0 = frame.width - MARGIN - BAR_WIDTH;
+    let y0 = frame.height - MARGIN - height;
+    let tick_row = |t: f32| y0 + ((1.0 - t) * (height - 1) as f32).round() as usize;
+
+    for row in 0..height {
+        let color = legend.colormap.color(1.0 - row as f32 / (height - 1) as f32);
+        frame.color[(y0 + row) * frame.width + x0..][..BAR_WIDTH].fill(color);
+    }
+
+    //une valeur trop proche d'une autre déjà écrite (zéro près d'un quart) n'est pas répétée
+    let mut labelled_rows: Vec<usize> = Vec::new();
+    for (&(t, _), label) in ticks.iter().zip(&labels) {
+        let row = tick_row(t);
+        frame.color[row * frame.width + x0 - TICK_LENGTH..][..TICK_LENGTH].fill(TEXT_COLOR);
+
+        if labelled_rows.iter().all(|&other| other.abs_diff(row) > GLYPH_SIZE) {
+            let x = x0 - TICK_LENGTH - GAP - text_width(label);
+            let y = (row + 1).saturating_sub(GLYPH_SIZE / 2);
+            darken(frame, x.saturating_sub(1), y.saturating_sub(1), text_width(label) + 2, GLYPH_SIZE + 2);
+            draw_text(frame, x, y, label, TEXT_COLOR);
+            labelled_rows.push(row);
+        }
+    }
+
+    let label_x = (x0 + BAR_WIDTH).saturating_sub(text_width(&legend.label));
+    let label_y = y0 - GLYPH_SIZE / 2 - GAP - GLYPH_SIZE;
+    darken(frame, label_x.saturating_sub(1), label_y.saturating_sub(1), text_width(&legend.label) + 2, GLYPH_SIZE + 2);
+    draw_text(frame, label_x, label_y, &legend.label, TEXT_COLOR);
+}
+
+//valeur d'une graduation en peu de caractères : décimale pour les ordres de grandeur usuels, scientifique sinon
+fn tick_label(value: f32) -> String {
+    let magnitude = value.abs();
+    if magnitude == 0.0 {
+        "0".to_string()
+    } else if (1e-2..1e4).contains(&magnitude) {
+        let label = format!("{value:.3}");
+        label.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        format!("{value:.1e}")
+    }
+}
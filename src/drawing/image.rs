@@ -0,0 +1,44 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::drawing::FrameBuffer;
+
+//le buffer est en 0RGB sur 32 bits, les images sont écrites en RGB 8 bits
+fn rgb_bytes(frame: &FrameBuffer) -> Vec<u8> {
+    frame.color
+        .iter()
+        .flat_map(|&c| [(c >> 16) as u8, (c >> 8) as u8, c as u8])
+        .collect()
+}
+
+pub fn save_ppm(frame: &FrameBuffer, path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "P6\n{} {}\n255\n", frame.width, frame.height)?;
+    writer.write_all(&rgb_bytes(frame))?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn save_png(frame: &FrameBuffer, path: &Path) -> Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(writer, frame.width as u32, frame.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&rgb_bytes(frame))?;
+
+    Ok(())
+}
+
+pub fn save_image(frame: &FrameBuffer, path: &Path) -> Result<()> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("png") => save_png(frame, path),
+        Some("ppm") => save_ppm(frame, path),
+        _ => Err(anyhow::anyhow!("Format d'image non supporté pour '{}' (png ou ppm attendu)", path.display())),
+    }
+}
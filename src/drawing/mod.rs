@@ -1,12 +1,31 @@
 use derive_more::Constructor;
 
-use crate::camera::{Point2f, Primitive2f};
+use crate::{camera::{Point2f, Primitive2f}, objects::lerp_color};
 
+pub mod colormap;
+pub mod image;
+pub mod shading;
+pub mod text;
 
 //primitive dessinable sur l'écran
 pub enum Primitive2i{
     Line(Line2i),
     Point(Point2i),
+    Triangle(Triangle2i),
+}
+
+impl Primitive2i {
+    //première et dernière lignes de l'écran touchées par la primitive
+    pub fn rows(&self) -> (i32, i32) {
+        match self {
+            Primitive2i::Line(line) => (line.p0.p.1.min(line.p1.p.1), line.p0.p.1.max(line.p1.p.1)),
+            Primitive2i::Point(point) => (point.p.1, point.p.1),
+            Primitive2i::Triangle(triangle) => {
+                let ys = [triangle.p0.p.1, triangle.p1.p.1, triangle.p2.p.1];
+                (ys.into_iter().min().unwrap_or(0), ys.into_iter().max().unwrap_or(0))
+            }
+        }
+    }
 }
 
 #[derive(Constructor)]
@@ -15,12 +34,96 @@ pub struct Line2i {
     p1: Point2i,
 }
 
+#[derive(Constructor)]
+pub struct Triangle2i {
+    p0: Point2i,
+    p1: Point2i,
+    p2: Point2i,
+}
+
 #[derive(Constructor)]
 pub struct Point2i {
     p: (i32, i32),
+    depth: f32,
     color: u32,
 }
 
+impl Point2i {
+    pub fn position(&self) -> (i32, i32) {
+        self.p
+    }
+}
+
+//buffer de couleur et z-buffer associé, un pixel ne s'ecrit que s'il est plus proche que celui deja présent
+pub struct FrameBuffer {
+    pub color: Vec<u32>,
+    pub depth: Vec<f32>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FrameBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            color: vec![0; width * height],
+            depth: vec![f32::INFINITY; width * height],
+            width,
+            height,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.color.iter_mut().for_each(|e| *e = 0);
+        self.depth.iter_mut().for_each(|e| *e = f32::INFINITY);
+    }
+
+    //découpe le buffer en bandes horizontales de rows lignes (la dernière peut être plus courte), remplissables en parallèle
+    //un buffer vide n'a aucune bande
+    pub fn bands(&mut self, rows: usize) -> Vec<FrameBand<'_>> {
+        let (width, rows) = (self.width, rows.max(1));
+        if width == 0 {
+            return Vec::new();
+        }
+
+        self.color
+            .chunks_mut(rows * width)
+            .zip(self.depth.chunks_mut(rows * width))
+            .enumerate()
+            .map(|(i, (color, depth))| {
+                let y_start = i * rows;
+                FrameBand { y_end: y_start + color.len() / width, color, depth, width, y_start }
+            })
+            .collect()
+    }
+}
+
+//lignes [y_start; y_end[ d'un FrameBuffer, les coordonnées restent celles de l'écran entier
+pub struct FrameBand<'a> {
+    color: &'a mut [u32],
+    depth: &'a mut [f32],
+    width: usize,
+    y_start: usize,
+    y_end: usize,
+}
+
+impl FrameBand<'_> {
+    //test de profondeur puis écriture, les coordonnées hors de la bande sont ignorées
+    fn set_pixel(&mut self, x: i32, y: i32, depth: f32, color: u32) {
+        if 0 <= x && x < self.width as i32 && self.y_start as i32 <= y && y < self.y_end as i32 {
+            let idx = (y as usize - self.y_start) * self.width + (x as usize);
+
+            if depth < self.depth[idx] {
+                self.depth[idx] = depth;
+                self.color[idx] = color;
+            }
+        }
+    }
+
+    fn overlaps_rows(&self, y_min: i32, y_max: i32) -> bool {
+        y_max >= self.y_start as i32 && y_min < self.y_end as i32
+    }
+}
+
 pub fn projected_to_pixel(point: Point2f, width: i32, height: i32) -> Point2i {
     let aspect = width as f32 / height as f32;
     let u = (point.p.0 + aspect) / (2.0 * aspect);
@@ -29,7 +132,7 @@ pub fn projected_to_pixel(point: Point2f, width: i32, height: i32) -> Point2i {
     let i = (u * (width as f32 - 1.0)).round() as i32;
     let j = (v * (height as f32 - 1.0)).round() as i32;
 
-    Point2i::new((i, j), point.color)
+    Point2i::new((i, j), point.depth, point.color)
 }
 
 pub fn projected_primitive_to_screen_primitive(projected: Primitive2f, width: usize, height: usize) -> Primitive2i {
@@ -40,31 +143,42 @@ pub fn projected_primitive_to_screen_primitive(projected: Primitive2f, width: us
 
             Primitive2i::Line(Line2i::new(p0_pixel, p1_pixel))
         }
-        Primitive2f::Point(point) => Primitive2i::Point(projected_to_pixel(point, width as i32, height as i32))
+        Primitive2f::Point(point) => Primitive2i::Point(projected_to_pixel(point, width as i32, height as i32)),
+        Primitive2f::Triangle(triangle) => Primitive2i::Triangle(Triangle2i::new(
+            projected_to_pixel(triangle.p0, width as i32, height as i32),
+            projected_to_pixel(triangle.p1, width as i32, height as i32),
+            projected_to_pixel(triangle.p2, width as i32, height as i32),
+        )),
     }
 }
 
-pub fn draw_line(
-    buffer: &mut [u32],
-    width: i32,
-    height: i32,
-    mut x0: i32,
-    mut y0: i32,
-    x1: i32,
-    y1: i32,
-    color: u32,
-) {
+//bresenham, la profondeur et la couleur sont interpolées le long du segment
+//on interpole l'inverse de la profondeur (linéaire dans l'espace écran, voir Projector::depth) plutôt que la profondeur pour rester correct en perspective
+pub fn draw_line(frame: &mut FrameBand, line: &Line2i) {
+    let (mut x0, mut y0) = line.p0.p;
+    let (x1, y1) = line.p1.p;
+    if !frame.overlaps_rows(y0.min(y1), y0.max(y1)) {
+        return;
+    }
+
+    let (c0, c1) = (line.p0.color, line.p1.color);
+
     let dx = (x1 - x0).abs();
     let dy = (y1 - y0).abs();
     let sx = if x0 < x1 { 1 } else { -1 };
     let sy = if y0 < y1 { 1 } else { -1 };
     let mut err = dx - dy;
 
+    let steps = dx.max(dy).max(1) as f32;
+    let inv_z0 = 1.0 / line.p0.depth;
+    let inv_z1 = 1.0 / line.p1.depth;
+    let mut step = 0;
+
     loop {
-        if 0 <= x0 && x0 < width && 0 <= y0 && y0 < height {
-            let idx = (y0 as usize) * (width as usize) + (x0 as usize);
-            buffer[idx] = color;
-        }
+        let t = step as f32 / steps;
+        let depth = 1.0 / (inv_z0 + t * (inv_z1 - inv_z0));
+        let color = if c0 == c1 { c0 } else { lerp_color(c0, c1, t) };
+        frame.set_pixel(x0, y0, depth, color);
 
         if x0 == x1 && y0 == y1 { break; }
 
@@ -78,22 +192,134 @@ pub fn draw_line(
             err += dx;
             y0 += sy;
         }
+
+        step += 1;
     }
 }
 
-fn draw_point(buffer: &mut [u32], point: &Point2i, width: i32, height: i32) {
-    if point.p.0 >= 0 && point.p.0 < width && point.p.1 >=0 && point.p.1 < height {
-        let idx = (point.p.1 as usize) * (width as usize) + (point.p.0 as usize);
+//sommet d'un triangle pendant le balayage : position, inverse de la profondeur et couleur, tous interpolables linéairement dans l'espace écran
+#[derive(Clone, Copy)]
+struct ScanVertex {
+    x: f32,
+    y: f32,
+    inv_z: f32,
+    color: u32,
+}
+
+impl ScanVertex {
+    fn from_point(point: &Point2i) -> Self {
+        Self { x: point.p.0 as f32, y: point.p.1 as f32, inv_z: 1.0 / point.depth, color: point.color }
+    }
+
+    fn lerp(&self, other: &ScanVertex, t: f32) -> Self {
+        Self {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            inv_z: self.inv_z + (other.inv_z - self.inv_z) * t,
+            color: lerp_color(self.color, other.color, t),
+        }
+    }
+
+    //intersection de l'arête [self; other] avec la ligne y
+    fn at_y(&self, other: &ScanVertex, y: f32) -> Self {
+        if other.y == self.y { *self } else { self.lerp(other, (y - self.y) / (other.y - self.y)) }
+    }
+}
+
+//rasterisation par lignes de balayage, la couleur est interpolée entre les sommets (gouraud)
+//si les trois sommets ont la même couleur (ombrage plat) on ne l'interpole pas
+pub fn draw_triangle(frame: &mut FrameBand, triangle: &Triangle2i) {
+    let mut v = [&triangle.p0, &triangle.p1, &triangle.p2].map(ScanVertex::from_point);
+    v.sort_by(|a, b| a.y.total_cmp(&b.y));
+    let [top, middle, bottom] = v;
+
+    let flat = top.color == middle.color && middle.color == bottom.color;
+
+    let y_start = (top.y as i32).max(frame.y_start as i32);
+    let y_end = (bottom.y as i32).min(frame.y_end as i32 - 1);
+
+    for y in y_start..=y_end {
+        let yf = y as f32;
+        let long_edge = top.at_y(&bottom, yf);
+        let short_edge = if yf < middle.y { top.at_y(&middle, yf) } else { middle.at_y(&bottom, yf) };
+
+        let (left, right) = if long_edge.x <= short_edge.x { (long_edge, short_edge) } else { (short_edge, long_edge) };
+
+        let x_start = (left.x.ceil() as i32).max(0);
+        let x_end = (right.x.floor() as i32).min(frame.width as i32 - 1);
+        let span = right.x - left.x;
+
+        for x in x_start..=x_end {
+            let t = if span > 0.0 { (x as f32 - left.x) / span } else { 0.0 };
+            let inv_z = left.inv_z + (right.inv_z - left.inv_z) * t;
+            let color = if flat { top.color } else { lerp_color(left.color, right.color, t) };
 
-        if buffer[idx] == 0 {
-            buffer[idx] = point.color;
+            frame.set_pixel(x, y, 1.0 / inv_z, color);
         }
     }
 }
 
-pub fn draw_primitive(primitive: &Primitive2i, buffer: &mut [u32], width: usize, height: usize) {
+pub fn draw_point(frame: &mut FrameBand, point: &Point2i) {
+    frame.set_pixel(point.p.0, point.p.1, point.depth, point.color);
+}
+
+pub fn draw_primitive(primitive: &Primitive2i, frame: &mut FrameBuffer) {
+    let height = frame.height;
+    for mut band in frame.bands(height) {
+        draw_primitive_in_band(primitive, &mut band);
+    }
+}
+
+//seule la partie de la primitive comprise dans la bande est dessinée
+pub fn draw_primitive_in_band(primitive: &Primitive2i, frame: &mut FrameBand) {
     match primitive {
-        Primitive2i::Line(line) => draw_line(buffer, width as i32, height as i32, line.p0.p.0, line.p0.p.1, line.p1.p.0, line.p1.p.1, line.p0.color),
-        Primitive2i::Point(point) => draw_point(buffer, point, width as i32, height as i32),
+        Primitive2i::Line(line) => draw_line(frame, line),
+        Primitive2i::Point(point) => draw_point(frame, point),
+        Primitive2i::Triangle(triangle) => draw_triangle(frame, triangle),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEAR_COLOR: u32 = 0xff0000;
+    const FAR_COLOR: u32 = 0x0000ff;
+
+    fn triangle(corners: [(i32, i32); 3], depth: f32, color: u32) -> Primitive2i {
+        let [p0, p1, p2] = corners.map(|p| Point2i::new(p, depth, color));
+        Primitive2i::Triangle(Triangle2i::new(p0, p1, p2))
+    }
+
+    fn pixel(frame: &FrameBuffer, x: usize, y: usize) -> u32 {
+        frame.color[y * frame.width + x]
     }
-}
\ No newline at end of file
+
+    //le résultat ne dépend pas de l'ordre de dessin : le plus proche l'emporte là où les primitives se recouvrent
+    #[test]
+    fn nearer_primitive_hides_farther_in_any_order() {
+        let near = || triangle([(0, 0), (15, 0), (0, 15)], 2.0, NEAR_COLOR);
+        let far = || triangle([(5, 5), (19, 5), (5, 19)], 5.0, FAR_COLOR);
+        let line = || Primitive2i::Line(Line2i::new(Point2i::new((0, 12), 1.0, NEAR_COLOR), Point2i::new((19, 12), 1.0, NEAR_COLOR)));
+        let point = || Primitive2i::Point(Point2i::new((7, 7), 8.0, FAR_COLOR));
+
+        let orders: [Vec<Primitive2i>; 2] = [vec![near(), far(), line(), point()], vec![point(), line(), far(), near()]];
+        let frames = orders.map(|primitives| {
+            let mut frame = FrameBuffer::new(20, 20);
+            for primitive in &primitives {
+                draw_primitive(primitive, &mut frame);
+            }
+            frame
+        });
+
+        for frame in &frames {
+            assert_eq!(pixel(frame, 6, 6), NEAR_COLOR); //les deux triangles, le proche devant
+            assert_eq!(pixel(frame, 12, 6), FAR_COLOR); //le lointain seul
+            assert_eq!(pixel(frame, 6, 12), NEAR_COLOR); //la ligne devant le triangle lointain
+            assert_eq!(pixel(frame, 7, 7), NEAR_COLOR); //le point derrière les deux triangles reste caché
+            assert_eq!(frame.depth[7 * 20 + 7], 2.0);
+        }
+        assert_eq!(frames[0].color, frames[1].color);
+        assert_eq!(frames[0].depth, frames[1].depth);
+    }
+}
@@ -0,0 +1,112 @@
+use std::{fmt, str::FromStr};
+
+use derive_more::Constructor;
+use nalgebra::Vector3;
+
+use crate::objects::{Point3f, Primitive3f, Triangle3f};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RenderMode {
+    #[default]
+    Wireframe,
+    Flat,
+    Gouraud,
+}
+
+impl RenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            RenderMode::Wireframe => RenderMode::Flat,
+            RenderMode::Flat => RenderMode::Gouraud,
+            RenderMode::Gouraud => RenderMode::Wireframe,
+        }
+    }
+}
+
+impl FromStr for RenderMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "wireframe" => Ok(RenderMode::Wireframe),
+            "flat" => Ok(RenderMode::Flat),
+            "gouraud" => Ok(RenderMode::Gouraud),
+            other => Err(anyhow::anyhow!("Mode de rendu '{}' inconnu (wireframe, flat ou gouraud)", other)),
+        }
+    }
+}
+
+impl fmt::Display for RenderMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RenderMode::Wireframe => "wireframe",
+            RenderMode::Flat => "flat",
+            RenderMode::Gouraud => "gouraud",
+        };
+        write!(f, "{name}")
+    }
+}
+
+//lumière directionnelle exprimée dans le repère du monde, direction = sens de propagation des rayons
+#[derive(Constructor, Clone, Copy)]
+pub struct DirectionalLight {
+    pub direction: Vector3<f32>,
+    pub ambient: f32,
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self::new(Vector3::new(-1.0, -1.0, -1.0).normalize(), 0.15)
+    }
+}
+
+impl DirectionalLight {
+    //eclairage lambertien double face (les surfaces comme mobius ou boy ne sont pas orientables)
+    pub fn intensity(&self, normal: &Vector3<f32>) -> f32 {
+        let diffuse = normal.dot(&-self.direction.normalize()).abs();
+        self.ambient + (1.0 - self.ambient) * diffuse
+    }
+}
+
+pub fn scale_color(color: u32, intensity: f32) -> u32 {
+    let channel = |shift: u32| {
+        let c = ((color >> shift) & 0xff) as f32;
+        ((c * intensity).round().clamp(0.0, 255.0) as u32) << shift
+    };
+
+    channel(16) | channel(8) | channel(0)
+}
+
+//calcule les couleurs des sommets d'un triangle (dans le repère du monde), les autres primitives ne sont pas éclairées
+pub fn shade_primitive(primitive: Primitive3f, light: &DirectionalLight, mode: RenderMode) -> Primitive3f {
+    match primitive {
+        Primitive3f::Triangle(triangle) => Primitive3f::Triangle(shade_triangle(triangle, light, mode)),
+        other => other,
+    }
+}
+
+fn shade_triangle(triangle: Triangle3f, light: &DirectionalLight, mode: RenderMode) -> Triangle3f {
+    let face_normal = triangle.face_normal().try_normalize(f32::EPSILON)
+        .or_else(|| triangle.normals.iter().sum::<Vector3<f32>>().try_normalize(f32::EPSILON))
+        .unwrap_or_else(Vector3::zeros);
+
+    let shade = |point: &Point3f, normal: &Vector3<f32>| {
+        let normal = if normal.norm_squared() > 0.0 { normal } else { &face_normal };
+        Point3f::new(point.p, scale_color(point.color, light.intensity(normal)))
+    };
+
+    match mode {
+        RenderMode::Gouraud => Triangle3f::new(
+            shade(&triangle.p0, &triangle.normals[0]),
+            shade(&triangle.p1, &triangle.normals[1]),
+            shade(&triangle.p2, &triangle.normals[2]),
+            triangle.normals,
+        ),
+        _ => Triangle3f::new(
+            shade(&triangle.p0, &face_normal),
+            shade(&triangle.p1, &face_normal),
+            shade(&triangle.p2, &face_normal),
+            triangle.normals,
+        ),
+    }
+}
@@ -0,0 +1,95 @@
+use font8x8::{BASIC_FONTS, GREEK_FONTS, LATIN_FONTS, UnicodeFonts};
+
+use crate::drawing::FrameBuffer;
+
+pub const GLYPH_SIZE: usize = 8;
+const LINE_HEIGHT: usize = GLYPH_SIZE + 3;
+const PADDING: usize = 5;
+const MARGIN: usize = 8;
+pub const TEXT_COLOR: u32 = 0xffffff;
+
+//coin de l'écran où s'accroche un panneau de texte
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+//glyphe 8x8 d'un caractère, une ligne par octet avec le bit de poids faible à gauche
+//un '?' remplace les caractères que la police ne connaît pas
+fn glyph(c: char) -> [u8; 8] {
+    BASIC_FONTS
+        .get(c)
+        .or_else(|| LATIN_FONTS.get(c))
+        .or_else(|| GREEK_FONTS.get(c))
+        .unwrap_or_else(|| BASIC_FONTS.get('?').unwrap())
+}
+
+//largeur en pixels d'une ligne de texte
+pub fn text_width(text: &str) -> usize {
+    text.chars().count() * GLYPH_SIZE
+}
+
+//texte sur une ligne à partir du coin haut gauche (x, y), par dessus la scène (sans test de profondeur)
+//les pixels hors de l'écran sont ignorés
+pub fn draw_text(frame: &mut FrameBuffer, x: usize, y: usize, text: &str, color: u32) {
+    for (i, c) in text.chars().enumerate() {
+        let x0 = x + i * GLYPH_SIZE;
+
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in (0..GLYPH_SIZE).filter(|column| bits >> column & 1 == 1) {
+                let (px, py) = (x0 + column, y + row);
+                if px < frame.width && py < frame.height {
+                    frame.color[py * frame.width + px] = color;
+                }
+            }
+        }
+    }
+}
+
+//assombrit un rectangle pour que le texte reste lisible quelle que soit la scène derrière
+pub(crate) fn darken(frame: &mut FrameBuffer, x: usize, y: usize, width: usize, height: usize) {
+    for py in y..(y + height).min(frame.height) {
+        for px in x..(x + width).min(frame.width) {
+            let pixel = &mut frame.color[py * frame.width + px];
+            *pixel = (*pixel >> 2) & 0x3f3f3f;
+        }
+    }
+}
+
+//lignes de texte sur fond assombri, dans un coin de l'écran
+//une ligne trop longue pour la fenêtre est coupée par son début, la fin d'un chemin de fichier étant la plus parlante
+pub fn draw_panel(frame: &mut FrameBuffer, lines: &[impl AsRef<str>], corner: Corner) {
+    let max_chars = frame.width.saturating_sub(2 * (MARGIN + PADDING)) / GLYPH_SIZE;
+    if lines.is_empty() || max_chars < 4 {
+        return;
+    }
+
+    let lines: Vec<String> = lines.iter().map(|line| shortened(line.as_ref(), max_chars)).collect();
+    let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0) + 2 * PADDING;
+    let height = lines.len() * LINE_HEIGHT + 2 * PADDING - (LINE_HEIGHT - GLYPH_SIZE);
+
+    let x = match corner {
+        Corner::TopLeft | Corner::BottomLeft => MARGIN,
+        Corner::TopRight | Corner::BottomRight => frame.width.saturating_sub(MARGIN + width),
+    };
+    let y = match corner {
+        Corner::TopLeft | Corner::TopRight => MARGIN,
+        Corner::BottomLeft | Corner::BottomRight => frame.height.saturating_sub(MARGIN + height),
+    };
+
+    darken(frame, x, y, width, height);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(frame, x + PADDING, y + PADDING + i * LINE_HEIGHT, line, TEXT_COLOR);
+    }
+}
+
+fn shortened(line: &str, max_chars: usize) -> String {
+    let count = line.chars().count();
+    match count <= max_chars {
+        true => line.to_string(),
+        false => format!("...{}", line.chars().skip(count + 3 - max_chars).collect::<String>()),
+    }
+}
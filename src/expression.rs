@@ -0,0 +1,459 @@
+use std::{f32::consts::{E, PI, TAU}, fmt, str::FromStr};
+
+use anyhow::Result;
+
+//expressions mathématiques lues à l'exécution, pour définir des géométries sans recompiler
+//
+//  opérateurs : + - * / ^ (ou **), moins unaire, parenthèses, multiplication implicite ("2u", "3(u+v)", "2 pi")
+//  fonctions : sin cos tan asin acos atan sinh cosh tanh exp ln log sqrt cbrt abs sign floor ceil, atan2 min max pow
+//  constantes : pi tau e phi, plus celles fournies à la compilation
+//
+//les variables (par exemple u et v) sont résolues à la compilation, l'évaluation prend leurs valeurs dans le même ordre
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Constant(f32),
+    Variable(usize),
+    Negate(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Sin, Cos, Tan, Asin, Acos, Atan, Sinh, Cosh, Tanh,
+    Exp, Ln, Log, Sqrt, Cbrt, Abs, Sign, Floor, Ceil,
+    Atan2, Min, Max, Pow,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        use Function::*;
+
+        Some(match name {
+            "sin" => Sin, "cos" => Cos, "tan" => Tan,
+            "asin" => Asin, "acos" => Acos, "atan" => Atan,
+            "sinh" => Sinh, "cosh" => Cosh, "tanh" => Tanh,
+            "exp" => Exp, "ln" => Ln, "log" => Log,
+            "sqrt" => Sqrt, "cbrt" => Cbrt, "abs" => Abs, "sign" => Sign,
+            "floor" => Floor, "ceil" => Ceil,
+            "atan2" => Atan2, "min" => Min, "max" => Max, "pow" => Pow,
+            _ => return None,
+        })
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Atan2 | Function::Min | Function::Max | Function::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[f32]) -> f32 {
+        use Function::*;
+
+        let a = args[0];
+        match self {
+            Sin => a.sin(), Cos => a.cos(), Tan => a.tan(),
+            Asin => a.asin(), Acos => a.acos(), Atan => a.atan(),
+            Sinh => a.sinh(), Cosh => a.cosh(), Tanh => a.tanh(),
+            Exp => a.exp(), Ln => a.ln(), Log => a.log10(),
+            Sqrt => a.sqrt(), Cbrt => a.cbrt(), Abs => a.abs(),
+            Sign => if a == 0.0 { 0.0 } else { a.signum() },
+            Floor => a.floor(), Ceil => a.ceil(),
+            Atan2 => a.atan2(args[1]),
+            Min => a.min(args[1]),
+            Max => a.max(args[1]),
+            Pow => a.powf(args[1]),
+        }
+    }
+}
+
+const BUILTIN_CONSTANTS: [(&str, f32); 4] = [("pi", PI), ("tau", TAU), ("e", E), ("phi", 1.618_034)];
+
+impl Expression {
+    //compile une expression, les noms de `variables` désignent les arguments de `eval` dans cet ordre
+    pub fn compile(source: &str, variables: &[&str], constants: &[(String, f32)]) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, position: 0, variables, constants };
+
+        let root = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            return Err(anyhow::anyhow!("'{}' inattendu dans '{}'", token, source));
+        }
+
+        Ok(Self { source: source.trim().to_string(), root })
+    }
+
+    //expression sans variable, évaluée immédiatement (bornes d'intervalles, constantes nommées...)
+    pub fn evaluate_constant(source: &str, constants: &[(String, f32)]) -> Result<f32> {
+        Ok(Self::compile(source, &[], constants)?.eval(&[]))
+    }
+
+    pub fn eval(&self, variables: &[f32]) -> f32 {
+        self.root.eval(variables)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Node {
+    fn eval(&self, variables: &[f32]) -> f32 {
+        match self {
+            Node::Constant(c) => *c,
+            Node::Variable(i) => variables[*i],
+            Node::Negate(a) => -a.eval(variables),
+            Node::Binary(op, a, b) => {
+                let (a, b) = (a.eval(variables), b.eval(variables));
+                match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Pow => pow(a, b),
+                }
+            }
+            Node::Call(function, args) => {
+                let values: Vec<f32> = args.iter().map(|arg| arg.eval(variables)).collect();
+                function.apply(&values)
+            }
+        }
+    }
+}
+
+//puissances entières calculées par powi, ce qui permet les bases négatives (u^3 avec u < 0)
+fn pow(a: f32, b: f32) -> f32 {
+    if b.fract() == 0.0 && b.abs() < i32::MAX as f32 {
+        a.powi(b as i32)
+    } else {
+        a.powf(b)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f32),
+    Identifier(String),
+    Operator(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Identifier(name) => write!(f, "{name}"),
+            Token::Operator(op) => write!(f, "{op}"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    let exponent_sign = (c == '-' || c == '+') && number.ends_with(['e', 'E']);
+                    if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                        //"2e" suivi d'autre chose qu'un chiffre est 2 * e, pas un exposant
+                        if (c == 'e' || c == 'E') && !exponent_follows(&chars) {
+                            break;
+                        }
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let value = f32::from_str(&number).map_err(|_| anyhow::anyhow!("Nombre '{}' invalide", number))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        name.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Identifier(name));
+            }
+            '*' => {
+                chars.next();
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    tokens.push(Token::Operator('^'));
+                } else {
+                    tokens.push(Token::Operator('*'));
+                }
+            }
+            '+' | '-' | '/' | '^' => {
+                chars.next();
+                tokens.push(Token::Operator(c));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LeftParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RightParen);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            other => return Err(anyhow::anyhow!("Caractère '{}' inattendu dans '{}'", other, source)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+//regarde après un 'e' s'il s'agit de l'exposant d'un nombre (e3, e-3, e+3)
+fn exponent_follows(chars: &std::iter::Peekable<std::str::Chars<'_>>) -> bool {
+    let mut lookahead = chars.clone();
+    lookahead.next();
+
+    match lookahead.next() {
+        Some(c) if c.is_ascii_digit() => true,
+        Some('-') | Some('+') => lookahead.next().is_some_and(|c| c.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+//descente récursive :
+//  expression := terme (('+' | '-') terme)*
+//  terme      := unaire (('*' | '/') unaire | unaire)*     (le second cas est la multiplication implicite)
+//  unaire     := ('-' | '+') unaire | puissance
+//  puissance  := primaire ('^' unaire)?                    (associative à droite, -u^2 = -(u^2))
+//  primaire   := nombre | nom | nom '(' expression (',' expression)* ')' | '(' expression ')'
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: &'a [&'a str],
+    constants: &'a [(String, f32)],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(anyhow::anyhow!("'{}' attendu, '{}' trouvé", expected, token)),
+            None => Err(anyhow::anyhow!("'{}' attendu en fin d'expression", expected)),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Node> {
+        let mut node = self.term()?;
+
+        while let Some(Token::Operator(op @ ('+' | '-'))) = self.peek() {
+            let op = if *op == '+' { BinaryOp::Add } else { BinaryOp::Sub };
+            self.next();
+            node = Node::Binary(op, Box::new(node), Box::new(self.term()?));
+        }
+
+        Ok(node)
+    }
+
+    fn term(&mut self) -> Result<Node> {
+        let mut node = self.unary()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator('*')) => { self.next(); BinaryOp::Mul }
+                Some(Token::Operator('/')) => { self.next(); BinaryOp::Div }
+                Some(Token::Number(_) | Token::Identifier(_) | Token::LeftParen) => BinaryOp::Mul,
+                _ => break,
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.next();
+                Ok(Node::Negate(Box::new(self.unary()?)))
+            }
+            Some(Token::Operator('+')) => {
+                self.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Node> {
+        let base = self.primary()?;
+
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.next();
+            return Ok(Node::Binary(BinaryOp::Pow, Box::new(base), Box::new(self.unary()?)));
+        }
+
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Node> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Node::Constant(value)),
+            Some(Token::LeftParen) => {
+                let node = self.expression()?;
+                self.expect(Token::RightParen)?;
+                Ok(node)
+            }
+            Some(Token::Identifier(name)) => {
+                //un nom qui n'est pas une fonction suivi d'une parenthèse est une multiplication implicite
+                if let (Some(Token::LeftParen), Some(function)) = (self.peek(), Function::from_name(&name)) {
+                    return self.call(&name, function);
+                }
+                self.identifier(&name)
+            }
+            Some(token) => Err(anyhow::anyhow!("'{}' inattendu", token)),
+            None => Err(anyhow::anyhow!("Expression incomplète")),
+        }
+    }
+
+    fn identifier(&self, name: &str) -> Result<Node> {
+        if let Some(index) = self.variables.iter().position(|&v| v == name) {
+            return Ok(Node::Variable(index));
+        }
+
+        self.constants
+            .iter()
+            .map(|(n, value)| (n.as_str(), *value))
+            .chain(BUILTIN_CONSTANTS)
+            .find(|&(n, _)| n == name)
+            .map(|(_, value)| Node::Constant(value))
+            .ok_or_else(|| anyhow::anyhow!("Nom '{}' inconnu (variables : {})", name, self.variables.join(", ")))
+    }
+
+    fn call(&mut self, name: &str, function: Function) -> Result<Node> {
+        self.expect(Token::LeftParen)?;
+
+        let mut args = vec![self.expression()?];
+        while let Some(Token::Comma) = self.peek() {
+            self.next();
+            args.push(self.expression()?);
+        }
+        self.expect(Token::RightParen)?;
+
+        if args.len() != function.arity() {
+            return Err(anyhow::anyhow!("'{}' prend {} argument(s), {} donné(s)", name, function.arity(), args.len()));
+        }
+
+        Ok(Node::Call(function, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, u: f32, v: f32) -> f32 {
+        Expression::compile(source, &["u", "v"], &[]).unwrap().eval(&[u, v])
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-5 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3", 0.0, 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0, 0.0), 9.0);
+        assert_eq!(eval("8 / 4 / 2", 0.0, 0.0), 1.0);
+        assert_eq!(eval("10 - 4 - 3", 0.0, 0.0), 3.0);
+        assert_eq!(eval("2^3^2", 0.0, 0.0), 512.0);
+        assert_eq!(eval("2**3", 0.0, 0.0), 8.0);
+        assert_eq!(eval("-u^2", 3.0, 0.0), -9.0);
+        assert_eq!(eval("2^-1", 0.0, 0.0), 0.5);
+        assert_eq!(eval("u^3", -2.0, 0.0), -8.0);
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        assert_eq!(eval("2u", 3.0, 0.0), 6.0);
+        assert_eq!(eval("3(u+v)", 1.0, 2.0), 9.0);
+        assert_eq!(eval("u v/5", 10.0, 2.0), 4.0);
+        assert!(close(eval("2 pi", 0.0, 0.0), TAU));
+        assert!(close(eval("v cos(u)", 0.0, 2.0), 2.0));
+    }
+
+    #[test]
+    fn numbers_and_exponents() {
+        assert_eq!(eval("1.5e3", 0.0, 0.0), 1500.0);
+        assert_eq!(eval("2e-1", 0.0, 0.0), 0.2);
+        assert!(close(eval("2e", 0.0, 0.0), 2.0 * E));
+    }
+
+    #[test]
+    fn functions_and_constants() {
+        assert!(close(eval("sin(pi/2)", 0.0, 0.0), 1.0));
+        assert!(close(eval("atan2(1, 1)", 0.0, 0.0), PI / 4.0));
+        assert_eq!(eval("max(u, v)", 1.0, 4.0), 4.0);
+        assert_eq!(eval("log(1000)", 0.0, 0.0), 3.0);
+
+        let constants = [("r".to_string(), 5.0)];
+        assert_eq!(Expression::evaluate_constant("2r", &constants).unwrap(), 10.0);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(Expression::compile("u +", &["u"], &[]).is_err());
+        assert!(Expression::compile("(u", &["u"], &[]).is_err());
+        assert!(Expression::compile("u)", &["u"], &[]).is_err());
+        assert!(Expression::compile("w", &["u", "v"], &[]).is_err());
+        assert!(Expression::compile("atan2(u)", &["u"], &[]).is_err());
+        assert!(Expression::compile("u $ 2", &["u"], &[]).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use nalgebra::Vector3;
+
+use crate::{camera::Projector, objects::{Line3f, Point3f, Primitive3f, Triangle3f}};
+
+//demi-espace normal.p + offset >= 0, avec une normale unitaire pour que la valeur soit une distance signée
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub offset: f32,
+}
+
+impl Plane {
+    pub fn new(normal: Vector3<f32>, offset: f32) -> Self {
+        let norm = normal.norm();
+        Self { normal: normal / norm, offset: offset / norm }
+    }
+
+    pub fn distance(&self, p: &Vector3<f32>) -> f32 {
+        self.normal.dot(p) + self.offset
+    }
+}
+
+//volume de vision, dans le repère de la camera : plans proche et lointain, puis les bords gauche, droit, bas et haut de l'image
+//aspect = largeur / hauteur, comme dans la projection (f x / w(z) dans [-aspect; aspect], f y / w(z) dans [-1; 1], voir Projector)
+//c'est une pyramide tronquée en perspective et un pavé en orthographique
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Frustum {
+    pub fn new(projector: &Projector, aspect: f32) -> Self {
+        let Projector { f, blend, focus, far } = *projector;
+        let (slope, offset) = (1.0 - blend, blend * focus); //w(z) = slope z + offset
+
+        Self {
+            planes: [
+                Plane::new(Vector3::new(0.0, 0.0, 1.0), -projector.near()),
+                Plane::new(Vector3::new(0.0, 0.0, -1.0), far),
+                Plane::new(Vector3::new(f, 0.0, aspect * slope), aspect * offset),
+                Plane::new(Vector3::new(-f, 0.0, aspect * slope), aspect * offset),
+                Plane::new(Vector3::new(0.0, f, slope), offset),
+                Plane::new(Vector3::new(0.0, -f, slope), offset),
+            ],
+        }
+    }
+
+    pub fn contains(&self, p: &Vector3<f32>) -> bool {
+        self.planes.iter().all(|plane| plane.distance(p) >= 0.0)
+    }
+
+    //test conservatif : une sphère déclarée visible peut se trouver juste à l'extérieur, près d'une arête du volume
+    pub fn intersects_sphere(&self, center: &Vector3<f32>, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.distance(center) >= -radius)
+    }
+
+    //découpe une primitive exprimée dans le repère de la camera, un triangle peut devenir un polygone de jusqu'à 9 sommets, d'où l'itérateur
+    pub fn clip_primitive(&self, primitive: Primitive3f) -> impl Iterator<Item = Primitive3f> + use<> {
+        let clipped: Vec<Primitive3f> = match primitive {
+            Primitive3f::Point(point) => match self.contains(&point.p) {
+                true => return either::Left(Some(Primitive3f::Point(point)).into_iter()),
+                false => return either::Left(None.into_iter()),
+            },
+            Primitive3f::Line(line) => return either::Left(self.clip_line(line).map(Primitive3f::Line).into_iter()),
+            Primitive3f::Triangle(triangle) => {
+                if [&triangle.p0, &triangle.p1, &triangle.p2].iter().all(|v| self.contains(&v.p)) {
+                    return either::Left(Some(Primitive3f::Triangle(triangle)).into_iter());
+                }
+                self.clip_triangle(triangle).map(Primitive3f::Triangle).collect()
+            }
+        };
+
+        either::Right(clipped.into_iter())
+    }
+
+    //Liang-Barsky : on réduit l'intervalle [t0; t1] du segment plan par plan
+    pub fn clip_line(&self, line: Line3f) -> Option<Line3f> {
+        let (mut t0, mut t1) = (0.0f32, 1.0f32);
+
+        for plane in &self.planes {
+            let (d0, d1) = (plane.distance(&line.p0.p), plane.distance(&line.p1.p));
+            match (d0 >= 0.0, d1 >= 0.0) {
+                (true, true) => {}
+                (false, false) => return None,
+                (false, true) => t0 = t0.max(d0 / (d0 - d1)),
+                (true, false) => t1 = t1.min(d0 / (d0 - d1)),
+            }
+            if t0 > t1 {
+                return None;
+            }
+        }
+
+        match (t0 > 0.0, t1 < 1.0) {
+            (false, false) => Some(line),
+            (start, end) => {
+                let p0 = if start { line.p0.lerp(&line.p1, t0) } else { line.p0.clone() };
+                let p1 = if end { line.p0.lerp(&line.p1, t1) } else { line.p1 };
+                Some(Line3f::new(p0, p1))
+            }
+        }
+    }
+
+    //Sutherland-Hodgman sur les six plans, le polygone restant est découpé en éventail
+    pub fn clip_triangle(&self, triangle: Triangle3f) -> impl Iterator<Item = Triangle3f> + use<> {
+        let normals = triangle.normals;
+        let mut polygon = vec![triangle.p0, triangle.p1, triangle.p2];
+
+        for plane in &self.planes {
+            if polygon.is_empty() {
+                break;
+            }
+
+            let mut clipped: Vec<Point3f> = Vec::with_capacity(polygon.len() + 1);
+            for (i, current) in polygon.iter().enumerate() {
+                let next = &polygon[(i + 1) % polygon.len()];
+                let (d_current, d_next) = (plane.distance(&current.p), plane.distance(&next.p));
+
+                if d_current >= 0.0 {
+                    clipped.push(current.clone());
+                }
+                if (d_current >= 0.0) != (d_next >= 0.0) {
+                    clipped.push(current.lerp(next, d_current / (d_current - d_next)));
+                }
+            }
+            polygon = clipped;
+        }
+
+        (1..polygon.len().saturating_sub(1))
+            .map(move |i| Triangle3f::new(polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone(), normals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //perspective de champ 90° et d'aspect 1 : le volume est |x| <= z, |y| <= z, near <= z <= 100
+    fn projector() -> Projector {
+        Projector { f: 1.0, blend: 0.0, focus: 10.0, far: 100.0 }
+    }
+
+    fn point(x: f32, y: f32, z: f32) -> Point3f {
+        Point3f::new(Vector3::new(x, y, z), 0xffffff)
+    }
+
+    fn close(a: &Vector3<f32>, b: &Vector3<f32>) -> bool {
+        (a - b).norm() < 1e-5
+    }
+
+    #[test]
+    fn line_crossing_near_plane_is_cut_at_the_plane() {
+        let frustum = Frustum::new(&projector(), 1.0);
+
+        let line = frustum.clip_line(Line3f::new(point(0.0, 0.0, -5.0), point(0.0, 0.0, 5.0))).unwrap();
+        assert!(close(&line.p0.p, &Vector3::new(0.0, 0.0, projector().near())), "{}", line.p0.p);
+        assert!(close(&line.p1.p, &Vector3::new(0.0, 0.0, 5.0)));
+
+        let behind = Line3f::new(point(0.0, 0.0, -5.0), point(1.0, 0.0, -1.0));
+        assert!(frustum.clip_line(behind).is_none());
+    }
+
+    #[test]
+    fn triangle_outside_is_dropped() {
+        let frustum = Frustum::new(&projector(), 1.0);
+        let normals = [Vector3::z(); 3];
+
+        let left = Triangle3f::new(point(-50.0, 0.0, 10.0), point(-40.0, 1.0, 10.0), point(-45.0, -1.0, 20.0), normals);
+        let behind = Triangle3f::new(point(0.0, 0.0, -1.0), point(1.0, 0.0, -2.0), point(0.0, 1.0, -3.0), normals);
+
+        assert_eq!(frustum.clip_triangle(left).count(), 0);
+        assert_eq!(frustum.clip_triangle(behind).count(), 0);
+    }
+
+    //un sommet au delà du plan lointain : il reste un quadrilatère, découpé en deux triangles
+    #[test]
+    fn triangle_straddling_far_plane() {
+        let frustum = Frustum::new(&projector(), 1.0);
+        let triangle = Triangle3f::new(point(-5.0, 0.0, 90.0), point(5.0, 0.0, 90.0), point(0.0, 0.0, 110.0), [Vector3::z(); 3]);
+
+        let triangles: Vec<Triangle3f> = frustum.clip_triangle(triangle).collect();
+        let expected = [
+            [Vector3::new(-5.0, 0.0, 90.0), Vector3::new(5.0, 0.0, 90.0), Vector3::new(2.5, 0.0, 100.0)],
+            [Vector3::new(-5.0, 0.0, 90.0), Vector3::new(2.5, 0.0, 100.0), Vector3::new(-2.5, 0.0, 100.0)],
+        ];
+
+        assert_eq!(triangles.len(), expected.len());
+        for (triangle, expected) in triangles.iter().zip(expected) {
+            for (vertex, expected) in [&triangle.p0, &triangle.p1, &triangle.p2].iter().zip(expected) {
+                assert!(close(&vertex.p, &expected), "{} au lieu de {}", vertex.p, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use derive_more::Constructor;
+
+use crate::{camera::Camera, controls::CameraMode, drawing::{FrameBuffer, text::{Corner, draw_panel}}, render::RenderSettings, world::{NodeId, World}};
+
+//mesures du dernier rendu
+#[derive(Constructor, Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    pub primitives: usize,
+    pub duration: Duration,
+}
+
+impl FrameStats {
+    pub fn fps(&self) -> f64 {
+        1.0 / self.duration.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+//informations affichées en haut à gauche : rendu, camera, puis les objets de la scène
+//un objet masqué est précédé d'un '-', l'objet sélectionné d'un '>'
+fn hud_lines(world: &World, camera: &Camera, settings: &RenderSettings, stats: &FrameStats, camera_mode: Option<CameraMode>, selected: Option<NodeId>) -> Vec<String> {
+    let (position, forward) = (camera.position(), camera.forward());
+
+    let mut lines = vec![
+        format!("{:.1} FPS ({:.1} ms), {} primitives", stats.fps(), stats.duration.as_secs_f64() * 1000.0, stats.primitives),
+        match camera_mode {
+            Some(camera_mode) => format!("rendu {}, {}, camera {}", settings.mode, camera.projection(), camera_mode),
+            None => format!("rendu {}, {}", settings.mode, camera.projection()),
+        },
+        format!("position ({:.2}, {:.2}, {:.2})", position.x, position.y, position.z),
+        format!("visée ({:.2}, {:.2}, {:.2}), fovy {:.0}°", forward.x, forward.y, forward.z, camera.fovy().to_degrees()),
+    ];
+    if let Some(up_axis) = camera.up_axis() {
+        lines[3].push_str(&format!(", vertical {up_axis}"));
+    }
+
+    for (id, node) in world.nodes() {
+        let mark = match (Some(id) == selected, world.is_visible(id)) {
+            (true, _) => '>',
+            (false, false) => '-',
+            (false, true) => ' ',
+        };
+        lines.push(format!("{mark}[{}] {}", id + 1, node.name));
+    }
+
+    lines
+}
+
+//camera_mode n'a de sens que dans la fenêtre interactive
+pub fn draw_hud(frame: &mut FrameBuffer, world: &World, camera: &Camera, settings: &RenderSettings, stats: &FrameStats, camera_mode: Option<CameraMode>, selected: Option<NodeId>) {
+    draw_panel(frame, &hud_lines(world, camera, settings, stats, camera_mode, selected), Corner::TopLeft);
+}
@@ -0,0 +1,16 @@
+pub mod camera;
+pub mod controls;
+pub mod drawing;
+pub mod expression;
+pub mod frustum;
+pub mod hud;
+pub mod objects;
+pub mod overlays;
+pub mod render;
+pub mod scene;
+pub mod world;
+
+pub const WIDTH: usize = 1000;
+pub const HEIGHT: usize = 800;
+pub const EPSILON: f32 = 0.1; //distance du plan proche de la camera
+pub const DEFAULT_FAR: f32 = 10_000.0;
@@ -1,99 +1,742 @@
-use std::{f32::consts::{FRAC_PI_2, FRAC_PI_3}, primitive, thread::sleep, time::{self, Duration, SystemTime}};
-use minifb::{Window, WindowOptions};
+use std::{f32::consts::{FRAC_PI_3, PI}, path::{Path, PathBuf}, time::Instant};
+use clap::{Args, Parser, Subcommand};
+use minifb::{MouseButton, MouseMode, Window, WindowOptions};
 use nalgebra::{Matrix4, Vector3};
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use crate::{camera::{Camera, filter_primitive_3d, project_primitive}, drawing::{Primitive2i, draw_primitive, projected_primitive_to_screen_primitive}, objects::{Geometry, Object, Primitive3f, pointclouds::PointCloud, surfaces::Surface}};
+use manifolds_visualizer::{EPSILON, HEIGHT, WIDTH, camera::{Camera, Projection, UpAxis}, controls::{CameraMode, DOLLY_SPEED, LOOK_SPEED, MAX_FRAME_TIME, MOVE_SPEED, OrbitControls, PAN_SPEED, PROJECTION_SWITCH_TIME, TURN_SPEED, TURNTABLE_SPEED, speed_factor}, drawing::{FrameBuffer, colormap::Colormap, image::save_image, shading::RenderMode, text::{Corner, draw_panel}}, hud::{FrameStats, draw_hud}, overlays::{Overlays, draw_overlays}, objects::{ObjectSource, coloring::{ScalarField, SurfaceColoring}, curves::CurveSpec, geodesics::GeodesicSpec, implicit::ImplicitSpec, mesh_io::{ExportOptions, save_mesh}, surfaces::SurfaceSpec}, render::{RenderSettings, render_frame}, scene::{Scene, load_scene, save_scene}, world::{NodeId, World}};
 
-mod drawing;
-mod objects;
-mod camera;
+#[derive(Parser)]
+#[command(about = "Visualisation de surfaces de ℝ³ et de nuages de points", args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-pub const WIDTH: usize = 1000;
-pub const HEIGHT: usize = 800;
-pub const EPSILON: f32 = 0.1;
+    #[command(flatten)]
+    view: ViewArgs,
+}
 
-fn main() {
-    let mut window = Window::new("Manifolds-visualizer", WIDTH, HEIGHT, WindowOptions::default())
-        .unwrap_or_else(|e| panic!("Echec lors de la création de fenêtre : {}", e));
+#[derive(Subcommand)]
+enum Command {
+    /// Fenêtre interactive (commande par défaut)
+    View(ViewArgs),
+    /// Rendu sans fenêtre dans un fichier png ou ppm
+    Render(RenderArgs),
+    /// Géométrie différentielle d'une surface en un point (u, v) : formes fondamentales, normale et courbures
+    Probe(ProbeArgs),
+    /// Export d'une surface en maillage triangulé (.obj, .ply ou .stl)
+    Export(ExportArgs),
+}
+
+#[derive(Args)]
+struct ViewArgs {
+    #[command(flatten)]
+    scene: SceneArgs,
+
+    #[command(flatten)]
+    camera: CameraArgs,
+
+    #[arg(long, default_value_t = WIDTH)]
+    width: usize,
+
+    #[arg(long, default_value_t = HEIGHT)]
+    height: usize,
+
+    #[command(flatten)]
+    overlays: OverlayArgs,
+
+    /// Contrôle de la camera : orbit (souris autour d'une cible) ou fps (clavier), la touche C passe de l'un à l'autre
+    #[arg(long, default_value_t = CameraMode::default())]
+    camera_mode: CameraMode,
+
+    /// Redessine la scène à chaque image même si rien n'a changé (animations, mesure des performances), touche R
+    #[arg(long)]
+    continuous: bool,
+
+    /// Fichier où la touche F2 sauvegarde la scène (par défaut celui de --scene, sinon scene.txt)
+    #[arg(long, value_name = "PATH")]
+    save_to: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    #[command(flatten)]
+    scene: SceneArgs,
+
+    #[command(flatten)]
+    camera: CameraArgs,
+
+    /// Image de sortie, le format est déduit de l'extension (.png ou .ppm)
+    #[arg(short, long)]
+    output: PathBuf,
+
+    #[arg(long, default_value_t = WIDTH)]
+    width: usize,
+
+    #[arg(long, default_value_t = HEIGHT)]
+    height: usize,
+
+    #[command(flatten)]
+    overlays: OverlayArgs,
+
+    /// Ecrit par dessus l'image les informations de la fenêtre interactive (temps de rendu, camera, objets)
+    #[arg(long)]
+    hud: bool,
+}
+
+#[derive(Args)]
+struct ProbeArgs {
+    /// Surface à étudier, même syntaxe que pour --surface
+    #[arg(long)]
+    surface: SurfaceSpec,
+
+    /// Point du domaine de paramètrage, "u,v"
+    #[arg(long, value_name = "U,V", value_parser = parse_point, allow_hyphen_values = true)]
+    at: (f32, f32),
+
+    /// Morceau de la surface pour celles qui en ont plusieurs (cube_sphere)
+    #[arg(long, default_value_t = 0)]
+    part: usize,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// Surface à exporter, même syntaxe que pour --surface
+    #[arg(long)]
+    surface: SurfaceSpec,
+
+    /// Fichier de sortie, le format est déduit de l'extension (.obj, .ply ou .stl)
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Ecrit les normales aux sommets (obj, ply)
+    #[arg(long)]
+    normals: bool,
+
+    /// Ecrit les coordonnées (u, v) des sommets comme coordonnées de texture (obj, ply)
+    #[arg(long)]
+    uvs: bool,
+
+    /// Ecrit les couleurs des sommets, qui demandent --color-by (obj, ply)
+    #[arg(long)]
+    colors: bool,
+
+    /// PLY en texte plutôt qu'en binaire
+    #[arg(long)]
+    ascii: bool,
+
+    /// Fusionne les sommets confondus (coutures du paramètrage, pôles, morceaux d'une cube_sphere)
+    #[arg(long)]
+    weld: bool,
+
+    #[command(flatten)]
+    coloring: ColoringArgs,
+}
+
+//couple "a,b", pour un point (u, v) ou un intervalle
+fn parse_point(value: &str) -> anyhow::Result<(f32, f32)> {
+    let (u, v) = value.split_once(',').ok_or_else(|| anyhow::anyhow!("'a,b' attendu"))?;
+    Ok((u.trim().parse()?, v.trim().parse()?))
+}
+
+//coloration des surfaces par un champ scalaire
+#[derive(Args)]
+struct ColoringArgs {
+    /// Colore les surfaces de --surface par un champ scalaire :
+    /// gaussian, mean, k1, k2, u, v, height ou une fonction "expr:f(x,y,z,u,v)"
+    #[arg(long, value_name = "FIELD")]
+    color_by: Option<ScalarField>,
+
+    /// Palette pour --color-by : viridis, plasma, coolwarm ou diverging (centrée en zéro)
+    #[arg(long, default_value_t = Colormap::default())]
+    colormap: Colormap,
+
+    /// Intervalle de valeurs pour --color-by, "min,max" [défaut : calculé sur la surface]
+    #[arg(long, value_name = "MIN,MAX", value_parser = parse_point, allow_hyphen_values = true)]
+    range: Option<(f32, f32)>,
+}
+
+impl ColoringArgs {
+    fn coloring(&self) -> Option<SurfaceColoring> {
+        self.color_by.as_ref().map(|field| SurfaceColoring::new(field.clone(), self.colormap, self.range))
+    }
+}
+
+//repères dessinés par dessus la scène
+#[derive(Args)]
+struct OverlayArgs {
+    /// Axes X (rouge), Y (vert) et Z (bleu) du monde à l'origine, touche X dans la fenêtre
+    #[arg(long)]
+    axes: bool,
+
+    /// Grille dans le plan z = 0, touche G dans la fenêtre
+    #[arg(long)]
+    grid: bool,
+
+    /// Pas de la grille [défaut : choisi d'après la taille de la scène]
+    #[arg(long, value_name = "SPACING")]
+    grid_spacing: Option<f32>,
+
+    /// Gizmo d'orientation en bas à gauche, touche O dans la fenêtre
+    /// [défaut : affiché dans la fenêtre, pas par render], --gizmo=false pour le masquer
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    gizmo: Option<bool>,
+}
+
+impl OverlayArgs {
+    //default_gizmo : affichage du gizmo sans --gizmo, qui dépend de la commande
+    fn overlays(&self, default_gizmo: bool) -> anyhow::Result<Overlays> {
+        if let Some(spacing) = self.grid_spacing && (!spacing.is_finite() || spacing <= 0.0) {
+            return Err(anyhow::anyhow!("--grid-spacing doit être strictement positif"));
+        }
+        Ok(Overlays::new(self.axes, self.grid, self.gizmo.unwrap_or(default_gizmo), self.grid_spacing))
+    }
+}
+
+//objets à charger et mode de rendu
+#[derive(Args)]
+struct SceneArgs {
+    /// Fichier de scène à charger, les objets de --cloud, --mesh, --surface, etc. y sont ajoutés
+    #[arg(long, value_name = "PATH")]
+    scene: Option<PathBuf>,
+
+    /// Nuage de points à charger (fichier .ply), répétable
+    #[arg(long = "cloud", value_name = "PATH")]
+    clouds: Vec<String>,
+
+    /// Maillage triangulé à charger (.obj, .ply avec faces ou .stl), répétable
+    #[arg(long = "mesh", value_name = "PATH")]
+    meshes: Vec<String>,
+
+    /// Surface prédéfinie à afficher, répétable, ex. "torus:r=30,r_rev=10,u=60,v=30"
+    /// (sphere:r,u,v | torus:r,r_rev,u,v | mobius:r,w,u,v | cube_sphere:r,n | boy:scale,u,v),
+    /// ou définie par des expressions : "expr:x=v cos(u); y=v sin(u); z=2u; u_range=0..4pi; v_range=-5..5; u=80; v=10"
+    /// ou balayée le long d'une courbe (voir --curve) : "tube:r=1.5,v=12,frame=rmf|torus_knot:p=2,q=3", "ribbon:w=1,frame=frenet|trefoil"
+    #[arg(long = "surface", value_name = "SPEC")]
+    surfaces: Vec<SurfaceSpec>,
+
+    /// Surface implicite F(x,y,z)=0, répétable, ex. "f=x^2+y^2-z^2-1; bounds=-3..3; n=50"
+    /// ou prédéfinie : "gyroid; scale=5", "barth; scale=10", "tanglecube; scale=5"
+    #[arg(long = "implicit", value_name = "SPEC")]
+    implicits: Vec<ImplicitSpec>,
+
+    /// Courbe paramétrée à afficher, répétable, ex. "torus_knot:p=2,q=3,r=10,r_rev=4,n=400"
+    /// (torus_knot:p,q,r,r_rev,n | trefoil:scale,n | viviani:a,n | helix:r,pitch,turns,n),
+    /// ou définie par des expressions : "expr:x=5cos(t); y=5sin(t); z=t; t_range=0..6pi; n=300"
+    #[arg(long = "curve", value_name = "SPEC")]
+    curves: Vec<CurveSpec>,
+
+    /// Géodésiques tracées sur une surface, répétable : paramètres du tir puis la surface après un '|',
+    /// ex. "u=0,v=1,angle=0.5,length=80,fan=12|torus:r=30,r_rev=10" (fan : nombre de directions réparties autour du point)
+    #[arg(long = "geodesic", value_name = "SPEC")]
+    geodesics: Vec<GeodesicSpec>,
+
+    #[command(flatten)]
+    coloring: ColoringArgs,
+
+    /// Mode de rendu : wireframe, flat ou gouraud [défaut : wireframe, ou celui de la scène]
+    #[arg(long)]
+    mode: Option<RenderMode>,
+}
+
+impl SceneArgs {
+    //scène du fichier éventuel, complétée par les objets et réglages de la ligne de commande
+    fn load(&self, camera_args: &CameraArgs, aspect: f32) -> anyhow::Result<(World, Camera, RenderSettings)> {
+        let Scene { mut world, camera, mut settings } = match &self.scene {
+            Some(path) => load_scene(path)?,
+            None => Scene { world: World::new(), camera: None, settings: RenderSettings::default() },
+        };
+
+        for path in &self.clouds {
+            world.add_from_source(world.unused_name(path), ObjectSource::Cloud(path.clone()), Matrix4::identity(), 0xffffff, None)?;
+        }
+
+        for path in &self.meshes {
+            world.add_from_source(world.unused_name(path), ObjectSource::Mesh(path.clone()), Matrix4::identity(), 0xffffff, None)?;
+        }
+
+        for spec in &self.surfaces {
+            let id = world.add_from_source(world.unused_name(&spec.to_string()), ObjectSource::Surface(spec.clone()), Matrix4::identity(), 0xffffff, None)?;
+
+            if let Some(coloring) = self.coloring.coloring() {
+                world.set_coloring(id, coloring)?;
+            }
+        }
+
+        for spec in &self.implicits {
+            world.add_from_source(world.unused_name(&spec.to_string()), ObjectSource::Implicit(spec.clone()), Matrix4::identity(), 0xffffff, None)?;
+        }
+
+        for spec in &self.curves {
+            world.add_from_source(world.unused_name(&spec.to_string()), ObjectSource::Curve(spec.clone()), Matrix4::identity(), 0xffffff, None)?;
+        }
+
+        for spec in &self.geodesics {
+            world.add_from_source(world.unused_name(&spec.to_string()), ObjectSource::Geodesic(spec.clone()), Matrix4::identity(), 0xffff00, None)?;
+        }
+
+        if world.is_empty() {
+            return Err(anyhow::anyhow!("Rien à afficher, utiliser --scene, --cloud, --mesh, --surface, --implicit, --curve et/ou --geodesic (voir --help)"));
+        }
+
+        if let Some(mode) = self.mode {
+            settings.mode = mode;
+        }
+
+        if let Some(fovy) = camera_args.fovy && !(fovy > 0.0 && fovy < PI) {
+            return Err(anyhow::anyhow!("--fovy doit être compris strictement entre 0 et π"));
+        }
+        let mut camera = match camera {
+            Some(camera) if !camera_args.is_set() => camera,
+            _ => camera_args.camera(&world, aspect),
+        };
+        if let Some(far) = camera_args.far {
+            if far.is_nan() || far <= EPSILON {
+                return Err(anyhow::anyhow!("--far doit être supérieur à {}", EPSILON));
+            }
+            camera.set_far(far);
+        }
+        if let Some(up_axis) = camera_args.up_axis {
+            camera.set_up_axis(Some(up_axis));
+        }
+        if let Some(projection) = camera_args.projection {
+            camera.set_projection(projection);
+        }
+
+        Ok((world, camera, settings))
+    }
+}
+
+//pose initiale de la camera, qui regarde l'origine (voir Camera::new_looking_at_origin_from)
+//si aucune n'est donnée, la camera de la scène est utilisée
+#[derive(Args)]
+struct CameraArgs {
+    /// Champ de vision vertical en radians [défaut : π/3]
+    #[arg(long)]
+    fovy: Option<f32>,
 
-    let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
-    let mut camera = Camera::new_looking_at_origin_from(FRAC_PI_3, 0.0, 0.0, 0.0, 100.);
+    #[arg(long, allow_negative_numbers = true)]
+    roll: Option<f32>,
 
-    let cloud = PointCloud::new_from_path("./jardin.ply").unwrap();
-    let p3 = Object::new(Geometry::PointCloud(cloud), Matrix4::identity(), 0xffffff);
-    let primitives: Vec<_> = p3.primitives().collect();
+    #[arg(long, allow_negative_numbers = true)]
+    pitch: Option<f32>,
 
-    println!("Rendering {} points", primitives.len());
+    #[arg(long, allow_negative_numbers = true)]
+    yaw: Option<f32>,
+
+    /// Distance à l'origine [défaut : celle qui cadre tous les objets visibles]
+    #[arg(long)]
+    distance: Option<f32>,
+
+    /// Distance au-delà de laquelle rien n'est dessiné [défaut : 10000, ou celle de la scène]
+    #[arg(long)]
+    far: Option<f32>,
+
+    /// Axe du monde (x, y ou z) que la camera garde vertical : pas de roulis, lacet autour de cet axe [défaut : aucun, ou celui de la scène]
+    #[arg(long, value_name = "AXIS")]
+    up_axis: Option<UpAxis>,
+
+    /// Projection : perspective ou ortho (orthographique, la hauteur vue est réglée par la distance à la cible) [défaut : perspective, ou celle de la scène]
+    #[arg(long)]
+    projection: Option<Projection>,
+}
+
+impl CameraArgs {
+    fn is_set(&self) -> bool {
+        [self.fovy, self.roll, self.pitch, self.yaw, self.distance].iter().any(Option::is_some)
+    }
+
+    //sans distance donnée, la camera est reculée pour cadrer tous les objets visibles
+    fn camera(&self, world: &World, aspect: f32) -> Camera {
+        let mut camera = Camera::new_looking_at_origin_from(
+            self.fovy.unwrap_or(FRAC_PI_3),
+            self.roll.unwrap_or(0.0),
+            self.pitch.unwrap_or(0.0),
+            self.yaw.unwrap_or(0.0),
+            self.distance.unwrap_or(100.0),
+        );
+
+        if self.distance.is_none() && let Some(sphere) = world.visible_bounding_sphere() {
+            camera.frame(&sphere, aspect);
+        }
+
+        camera
+    }
+}
+
+//aide affichée en haut à droite de la fenêtre par F1
+const HELP: [&str; 18] = [
+    "F1        aide",
+    "H         informations",
+    "M         mode de rendu",
+    "C         camera orbit / fps",
+    "P         perspective / orthographique",
+    "Maj Ctrl  plus vite, moins vite",
+    "T         rotation automatique",
+    "R         rendu continu",
+    "X G O     axes, grille, gizmo",
+    "souris    gauche : tourner, droite : déplacer",
+    "molette   avancer / reculer",
+    "W/S A/D   avancer / reculer, déplacer",
+    "flèches   tourner",
+    "Tab       objet suivant",
+    "F         cadrer la sélection",
+    "Origine   cadrer tous les objets",
+    "1 à 9     masquer / afficher un objet",
+    "F2        sauvegarder la scène",
+];
+
+const OBJECT_KEYS: [minifb::Key; 9] = [
+    minifb::Key::Key1, minifb::Key::Key2, minifb::Key::Key3,
+    minifb::Key::Key4, minifb::Key::Key5, minifb::Key::Key6,
+    minifb::Key::Key7, minifb::Key::Key8, minifb::Key::Key9,
+];
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command.unwrap_or(Command::View(cli.view)) {
+        Command::View(args) => view(args),
+        Command::Render(args) => render(args),
+        Command::Probe(args) => probe(args),
+        Command::Export(args) => export(args),
+    }
+}
+
+fn export(args: ExportArgs) -> anyhow::Result<()> {
+    let mut surface = args.surface.build();
+    if let Some(coloring) = args.coloring.coloring() {
+        surface.set_coloring(&coloring);
+    }
+
+    let mesh = surface.to_mesh(args.weld);
+    let options = ExportOptions { normals: args.normals, uvs: args.uvs, colors: args.colors, ascii: args.ascii };
+    save_mesh(&mesh, &args.output, &options)?;
+    println!("{} sommets et {} triangles écrits dans {}", mesh.vertices().len(), mesh.faces().len(), args.output.display());
+
+    Ok(())
+}
+
+fn probe(args: ProbeArgs) -> anyhow::Result<()> {
+    let surface = args.surface.build();
+    let param = surface
+        .parts()
+        .get(args.part)
+        .ok_or_else(|| anyhow::anyhow!("La surface n'a que {} morceau(x)", surface.parts().len()))?;
+    let (u, v) = args.at;
+    let partials = param.partials(u, v);
+    let undefined = || "non définie (paramètrage dégénéré)".to_string();
+
+    println!("f     = {:?}", param.point(u, v).as_slice());
+    println!("fu    = {:?}", partials.fu.as_slice());
+    println!("fv    = {:?}", partials.fv.as_slice());
+    println!("fuu   = {:?}", partials.fuu.as_slice());
+    println!("fuv   = {:?}", partials.fuv.as_slice());
+    println!("fvv   = {:?}", partials.fvv.as_slice());
+    println!("n     = {}", partials.normal().map_or_else(undefined, |n| format!("{:?}", n.as_slice())));
+
+    let first = partials.first_fundamental_form();
+    println!("I     : E = {}, F = {}, G = {}", first.uu, first.uv, first.vv);
+    match partials.second_fundamental_form() {
+        Some(second) => println!("II    : L = {}, M = {}, N = {}", second.uu, second.uv, second.vv),
+        None => println!("II    : {}", undefined()),
+    }
+
+    println!("K     = {}", partials.gaussian_curvature().map_or_else(undefined, |k| k.to_string()));
+    println!("H     = {}", partials.mean_curvature().map_or_else(undefined, |h| h.to_string()));
+    match partials.principal_curvatures() {
+        Some(p) => {
+            println!("k1    = {} selon {:?}", p.k1, p.d1.as_slice());
+            println!("k2    = {} selon {:?}", p.k2, p.d2.as_slice());
+        }
+        None => println!("k1,k2 : {}", undefined()),
+    }
+
+    Ok(())
+}
+
+//une image vide n'a pas de sens et ne peut pas être écrite
+fn check_size(width: usize, height: usize) -> anyhow::Result<()> {
+    if width == 0 || height == 0 {
+        return Err(anyhow::anyhow!("--width et --height doivent être strictement positifs"));
+    }
+    Ok(())
+}
+
+fn render(args: RenderArgs) -> anyhow::Result<()> {
+    check_size(args.width, args.height)?;
+    let (world, camera, settings) = args.scene.load(&args.camera, args.width as f32 / args.height as f32)?;
+
+    let mut frame = FrameBuffer::new(args.width, args.height);
+    let start = Instant::now();
+    let primitives = render_frame(&world, &camera, &settings, &mut frame);
+    draw_overlays(&mut frame, &world, &camera, &args.overlays.overlays(false)?);
+    if args.hud {
+        draw_hud(&mut frame, &world, &camera, &settings, &FrameStats::new(primitives, start.elapsed()), None, None);
+    }
+
+    save_image(&frame, &args.output)?;
+    println!("Image écrite dans {}", args.output.display());
+
+    Ok(())
+}
+
+fn view(args: ViewArgs) -> anyhow::Result<()> {
+    check_size(args.width, args.height)?;
+    let (mut world, mut camera, mut settings) = args.scene.load(&args.camera, args.width as f32 / args.height as f32)?;
+    let (width, height) = (args.width, args.height);
+    let save_path = args.save_to.or(args.scene.scene).unwrap_or_else(|| PathBuf::from("scene.txt"));
+
+    let mut window = Window::new("Manifolds-visualizer", width, height, WindowOptions::default())
+        .unwrap_or_else(|e| panic!("Echec lors de la création de fenêtre : {}", e));
+    window.set_target_fps(60);
+
+    let mut frame = FrameBuffer::new(width, height);
+
+    for (id, node) in world.nodes() {
+        println!("[{}] {}", id + 1, node.name);
+    }
 
     let mut obsolete = true;
+    let mut camera_mode = args.camera_mode;
+    let center = world.visible_bounding_sphere().map_or_else(Vector3::zeros, |sphere| sphere.center);
+    let mut orbit = OrbitControls::new_facing(&camera, &center);
+    let mut selected: Option<NodeId> = None;
+    let mut previous_mouse = None;
+    let (mut show_hud, mut show_help) = (true, false);
+    let mut overlays = args.overlays.overlays(true)?;
+    let (mut continuous, mut turntable) = (args.continuous, false);
+    let mut last_frame = Instant::now();
 
     while window.is_open() && !window.is_key_down(minifb::Key::Escape) {
         window
-            .update_with_buffer(&buffer, WIDTH, HEIGHT)
+            .update_with_buffer(&frame.color, width, height)
             .unwrap_or_else(|e| panic!("Echec lors de l'actualisation du framebuffer : {}", e));
 
-            if obsolete {
-                let start = SystemTime::now();
-                buffer.iter_mut().for_each(|e| *e = 0);
-                world_primitives_to_screen_primitive(&primitives, &camera, WIDTH, HEIGHT)
-                    .for_each(|primitive| draw_primitive(&primitive, &mut buffer, WIDTH, HEIGHT));
-                let rate = 1.0 / start.elapsed().unwrap().as_secs_f64();
-                println!("{rate} FPS");
+            //les déplacements sont proportionnels à la durée réelle de l'image précédente, pas au nombre d'itérations
+            let now = Instant::now();
+            let dt = (now - last_frame).as_secs_f32().min(MAX_FRAME_TIME);
+            last_frame = now;
+
+            //en orbite, la mise au point suit la cible : c'est elle qui garde sa taille en changeant de projection, et s'en rapprocher zoome en orthographique
+            if camera_mode == CameraMode::Orbit {
+                camera.set_focus_distance(orbit.distance(&camera));
+            }
+            if camera.blend_projection(dt / PROJECTION_SWITCH_TIME) {
+                obsolete = true;
+            }
+
+            if obsolete || continuous {
+                let start = Instant::now();
+                let primitives = render_frame(&world, &camera, &settings, &mut frame);
+                let stats = FrameStats::new(primitives, start.elapsed());
+                draw_overlays(&mut frame, &world, &camera, &overlays);
+
+                if show_hud {
+                    draw_hud(&mut frame, &world, &camera, &settings, &stats, Some(camera_mode), selected);
+                }
+                if show_help {
+                    draw_panel(&mut frame, &HELP, Corner::TopRight);
+                }
                 obsolete = false;
             }
 
-            let speed = 0.5;
-            let angle_speed = 0.005;
+            //X, G et O affichent ou masquent les axes, la grille et le gizmo d'orientation
+            for (key, shown) in [(minifb::Key::X, &mut overlays.axes), (minifb::Key::G, &mut overlays.grid), (minifb::Key::O, &mut overlays.gizmo)] {
+                if window.is_key_pressed(key, minifb::KeyRepeat::No) {
+                    *shown = !*shown;
+                    obsolete = true;
+                }
+            }
 
-            if window.is_key_down(minifb::Key::W) {
-                camera.translate_relative(Vector3::new(0.0, 0.0, speed));
-                obsolete = true;
+            //R passe du rendu à chaque changement au rendu continu, T lance ou arrête la rotation automatique autour de la cible
+            if window.is_key_pressed(minifb::Key::R, minifb::KeyRepeat::No) {
+                continuous = !continuous;
+                println!("Rendu continu : {}", if continuous { "oui" } else { "non" });
             }
-            if window.is_key_down(minifb::Key::S) {
-                camera.translate_relative(Vector3::new(0.0, 0.0, -speed));
+            if window.is_key_pressed(minifb::Key::T, minifb::KeyRepeat::No) {
+                turntable = !turntable;
+                if turntable && camera_mode == CameraMode::FirstPerson {
+                    orbit.refocus(&camera);
+                }
+            }
+
+            //H affiche ou masque les informations, F1 l'aide
+            if window.is_key_pressed(minifb::Key::H, minifb::KeyRepeat::No) {
+                show_hud = !show_hud;
                 obsolete = true;
             }
-            if window.is_key_down(minifb::Key::A) {
-                camera.translate_relative(Vector3::new(-speed, 0.0, 0.0));
+            if window.is_key_pressed(minifb::Key::F1, minifb::KeyRepeat::No) {
+                show_help = !show_help;
                 obsolete = true;
             }
-            if window.is_key_down(minifb::Key::D) {
-                camera.translate_relative(Vector3::new(speed, 0.0, 0.0));
+
+            if window.is_key_pressed(minifb::Key::M, minifb::KeyRepeat::No) {
+                settings.mode = settings.mode.next();
+                println!("Render mode : {}", settings.mode);
                 obsolete = true;
             }
-            if window.is_key_down(minifb::Key::Up) {
-                camera.rotate_pitch(-angle_speed);
+
+            if window.is_key_pressed(minifb::Key::F2, minifb::KeyRepeat::No) {
+                save(&save_path, &world, &camera, &settings);
+            }
+
+            //les touches 1 à 9 masquent/affichent les objets de la scène
+            for (index, key) in OBJECT_KEYS.iter().enumerate().take(world.len()) {
+                if window.is_key_pressed(*key, minifb::KeyRepeat::No) {
+                    world.toggle_visibility(index);
+                    obsolete = true;
+                }
+            }
+
+            //Tab sélectionne l'objet suivant (puis aucun), F cadre la sélection ou tous les objets visibles, Origine cadre tous les objets visibles
+            if window.is_key_pressed(minifb::Key::Tab, minifb::KeyRepeat::No) {
+                selected = match selected {
+                    None if !world.is_empty() => Some(0),
+                    Some(id) if id + 1 < world.len() => Some(id + 1),
+                    _ => None,
+                };
+                match selected.and_then(|id| world.node(id)) {
+                    Some(node) => println!("Sélection : {}", node.name),
+                    None => println!("Sélection : aucune"),
+                }
                 obsolete = true;
             }
-            if window.is_key_down(minifb::Key::Down) {
-                camera.rotate_pitch(angle_speed);
+
+            let framed = if window.is_key_pressed(minifb::Key::Home, minifb::KeyRepeat::No) {
+                Some(world.visible_bounding_sphere())
+            } else if window.is_key_pressed(minifb::Key::F, minifb::KeyRepeat::No) {
+                Some(match selected {
+                    Some(id) => world.bounding_sphere(id),
+                    None => world.visible_bounding_sphere(),
+                })
+            } else {
+                None
+            };
+            if let Some(Some(sphere)) = framed {
+                orbit.target = camera.frame(&sphere, width as f32 / height as f32);
                 obsolete = true;
             }
-            if window.is_key_down(minifb::Key::Left) {
-                camera.rotate_yaw(angle_speed);
+
+            //P passe progressivement de la perspective à la projection orthographique et inversement
+            if window.is_key_pressed(minifb::Key::P, minifb::KeyRepeat::No) {
+                camera.switch_projection();
+                println!("Projection : {}", camera.projection());
+            }
+
+            if window.is_key_pressed(minifb::Key::C, minifb::KeyRepeat::No) {
+                camera_mode = camera_mode.next();
+                if camera_mode == CameraMode::Orbit {
+                    orbit.refocus(&camera);
+                }
+                println!("Camera : {camera_mode}");
                 obsolete = true;
             }
-            if window.is_key_down(minifb::Key::Right) {
-                camera.rotate_yaw(-angle_speed);
+
+            //Maj et Ctrl changent la vitesse, les translations au clavier suivent la taille de la scène
+            let fast = window.is_key_down(minifb::Key::LeftShift) || window.is_key_down(minifb::Key::RightShift);
+            let slow = window.is_key_down(minifb::Key::LeftCtrl) || window.is_key_down(minifb::Key::RightCtrl);
+            let factor = speed_factor(fast, slow) * dt;
+            let scale = world.visible_bounding_sphere().map_or(1.0, |sphere| sphere.radius).max(EPSILON);
+
+            if turntable {
+                orbit.spin(&mut camera, TURNTABLE_SPEED * factor);
                 obsolete = true;
             }
+
+            let speed = MOVE_SPEED * scale * factor;
+            let angle_speed = LOOK_SPEED * factor;
+
+            match camera_mode {
+                //glisser avec le bouton gauche tourne autour de la cible, avec le droit ou celui du milieu la déplace, la molette avance
+                //au clavier, les flèches tournent autour de la cible, W/S avancent et reculent, A/D déplacent latéralement
+                CameraMode::Orbit => {
+                    let mouse = window.get_mouse_pos(MouseMode::Discard);
+                    if let (Some(from), Some(to)) = (previous_mouse, mouse) && from != to {
+                        if window.get_mouse_down(MouseButton::Left) {
+                            orbit.drag_rotate(&mut camera, from, to, width, height);
+                            obsolete = true;
+                        } else if window.get_mouse_down(MouseButton::Right) || window.get_mouse_down(MouseButton::Middle) {
+                            orbit.pan(&mut camera, from, to, height);
+                            obsolete = true;
+                        }
+                    }
+                    previous_mouse = mouse;
+
+                    if let Some((_, scroll)) = window.get_scroll_wheel() && scroll != 0.0 {
+                        orbit.dolly(&mut camera, scroll.signum());
+                        obsolete = true;
+                    }
+
+                    let turn_speed = TURN_SPEED * factor;
+                    let pan_pixels = PAN_SPEED * factor;
+                    let dolly_steps = DOLLY_SPEED * factor;
+                    for (key, yaw, pitch) in [
+                        (minifb::Key::Left, turn_speed, 0.0),
+                        (minifb::Key::Right, -turn_speed, 0.0),
+                        (minifb::Key::Up, 0.0, turn_speed),
+                        (minifb::Key::Down, 0.0, -turn_speed),
+                    ] {
+                        if window.is_key_down(key) {
+                            orbit.turn(&mut camera, yaw, pitch);
+                            obsolete = true;
+                        }
+                    }
+                    for (key, steps) in [(minifb::Key::W, dolly_steps), (minifb::Key::S, -dolly_steps)] {
+                        if window.is_key_down(key) {
+                            orbit.dolly(&mut camera, steps);
+                            obsolete = true;
+                        }
+                    }
+                    for (key, dx) in [(minifb::Key::A, pan_pixels), (minifb::Key::D, -pan_pixels)] {
+                        if window.is_key_down(key) {
+                            orbit.pan(&mut camera, (0.0, 0.0), (dx, 0.0), height);
+                            obsolete = true;
+                        }
+                    }
+                }
+
+                CameraMode::FirstPerson => {
+                    if window.is_key_down(minifb::Key::W) {
+                        camera.translate_relative(Vector3::new(0.0, 0.0, speed));
+                        obsolete = true;
+                    }
+                    if window.is_key_down(minifb::Key::S) {
+                        camera.translate_relative(Vector3::new(0.0, 0.0, -speed));
+                        obsolete = true;
+                    }
+                    if window.is_key_down(minifb::Key::A) {
+                        camera.translate_relative(Vector3::new(-speed, 0.0, 0.0));
+                        obsolete = true;
+                    }
+                    if window.is_key_down(minifb::Key::D) {
+                        camera.translate_relative(Vector3::new(speed, 0.0, 0.0));
+                        obsolete = true;
+                    }
+                    if window.is_key_down(minifb::Key::Up) {
+                        camera.rotate_pitch(-angle_speed);
+                        obsolete = true;
+                    }
+                    if window.is_key_down(minifb::Key::Down) {
+                        camera.rotate_pitch(angle_speed);
+                        obsolete = true;
+                    }
+                    if window.is_key_down(minifb::Key::Left) {
+                        camera.rotate_yaw(angle_speed);
+                        obsolete = true;
+                    }
+                    if window.is_key_down(minifb::Key::Right) {
+                        camera.rotate_yaw(-angle_speed);
+                        obsolete = true;
+                    }
+                }
+            }
     }
-}
 
-//le pipeline de rendu pour un objet
-fn object_to_screen_primitives(object: &Object, camera: &Camera, width: usize, height: usize) -> impl Iterator<Item = Primitive2i> {
-    let perspective_center_distance = camera.perspective_center_distance;
-    let world_primitives = object.primitives();
-    let camera_primitives = world_primitives.map(move |primitive| camera.world_primitive_to_camera_coordinates(&primitive));
-    let camera_visible_primitives = camera_primitives.flat_map(filter_primitive_3d);
-    let camera_projected_primitives = camera_visible_primitives.map(move |primitive| project_primitive(primitive, perspective_center_distance));
-    camera_projected_primitives.map(move |projected| projected_primitive_to_screen_primitive(projected, width, height))
+    Ok(())
 }
 
-fn world_primitives_to_screen_primitive(primitives: &[Primitive3f], camera: &Camera, width: usize, height: usize) -> impl Iterator<Item = Primitive2i> {
-    let perspective_center_distance = camera.perspective_center_distance;
-    let camera_primitives = primitives.iter().map(move |primitive| camera.world_primitive_to_camera_coordinates(primitive));
-    let camera_visible_primitives = camera_primitives.flat_map(filter_primitive_3d);
-    let camera_projected_primitives = camera_visible_primitives.map(move |primitive| project_primitive(primitive, perspective_center_distance));
-    camera_projected_primitives.map(move |projected| projected_primitive_to_screen_primitive(projected, width, height))
-}
\ No newline at end of file
+fn save(path: &Path, world: &World, camera: &Camera, settings: &RenderSettings) {
+    match save_scene(path, world, camera, settings) {
+        Ok(()) => println!("Scène sauvegardée dans {}", path.display()),
+        Err(e) => eprintln!("{e:#}"),
+    }
+}
@@ -0,0 +1,164 @@
+use nalgebra::{Matrix4, Vector3};
+
+//boîte alignée sur les axes, vide tant qu'aucun point n'y a été ajouté
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self { min: Vector3::repeat(f32::INFINITY), max: Vector3::repeat(f32::NEG_INFINITY) }
+    }
+}
+
+impl BoundingBox {
+    //les points non finis (paramètrages dégénérés) sont ignorés
+    pub fn from_points(points: impl IntoIterator<Item = Vector3<f32>>) -> Self {
+        let mut bounds = Self::default();
+        for point in points {
+            bounds.extend(point);
+        }
+        bounds
+    }
+
+    pub fn extend(&mut self, point: Vector3<f32>) {
+        if point.iter().all(|c| c.is_finite()) {
+            self.min = self.min.inf(&point);
+            self.max = self.max.sup(&point);
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self { min: self.min.inf(&other.min), max: self.max.sup(&other.max) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn diagonal(&self) -> f32 {
+        if self.is_empty() { 0.0 } else { (self.max - self.min).norm() }
+    }
+
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+            Vector3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    //boîte alignée sur les axes qui contient l'image de celle-ci (plus grande qu'elle si la transformation tourne)
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        Self::from_points(self.corners().map(|corner| transform.transform_point(&corner.into()).coords))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    //centrée sur la boîte englobante, de rayon la plus grande distance au centre, None s'il n'y a aucun point
+    pub fn from_points(points: impl Iterator<Item = Vector3<f32>> + Clone) -> Option<Self> {
+        let bounds = BoundingBox::from_points(points.clone());
+        if bounds.is_empty() {
+            return None;
+        }
+
+        let center = bounds.center();
+        let radius = points
+            .filter(|p| p.iter().all(|c| c.is_finite()))
+            .map(|p| (p - center).norm())
+            .fold(0.0, f32::max);
+
+        Some(Self { center, radius })
+    }
+
+    //le rayon est multiplié par la norme de Frobenius de la partie linéaire, qui majore son plus grand facteur d'étirement
+    //(la norme des colonnes ne suffit pas : un cisaillement étire plus qu'aucun des axes)
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> Self {
+        let scale = transform.fixed_view::<3, 3>(0, 0).norm();
+
+        Self { center: transform.transform_point(&self.center.into()).coords, radius: self.radius * scale }
+    }
+
+    //plus petite sphère contenant les deux
+    pub fn merged(&self, other: &Self) -> Self {
+        let offset = other.center - self.center;
+        let distance = offset.norm();
+
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+
+        let radius = (distance + self.radius + other.radius) / 2.0;
+        Self { center: self.center + offset * ((radius - self.radius) / distance), radius }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use nalgebra::Matrix3;
+
+    use super::*;
+
+    //points répartis sur la sphère, pôles compris
+    fn sphere_points(sphere: &BoundingSphere, n: usize) -> Vec<Vector3<f32>> {
+        (0..=n)
+            .flat_map(|i| (0..2 * n).map(move |j| (PI * i as f32 / n as f32, PI * j as f32 / n as f32)))
+            .map(|(theta, phi)| sphere.center + sphere.radius * Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()))
+            .collect()
+    }
+
+    #[test]
+    fn transformed_sphere_contains_image() {
+        let sphere = BoundingSphere { center: Vector3::new(1.0, -2.0, 0.5), radius: 3.0 };
+        let shear = Matrix3::new(1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0);
+        let transforms = [
+            shear.to_homogeneous(),
+            Matrix4::new_translation(&Vector3::new(5.0, 0.0, -1.0)) * shear.to_homogeneous(),
+            Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 0.5, 1.0)),
+            Matrix4::new_rotation(Vector3::new(0.3, -0.2, 0.9)),
+        ];
+
+        for transform in transforms {
+            let image = sphere.transformed(&transform);
+            for point in sphere_points(&sphere, 32) {
+                let moved = transform.transform_point(&point.into()).coords;
+                assert!((moved - image.center).norm() <= image.radius * (1.0 + 1e-5), "{transform} : {moved} hors de {image:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn merged_sphere_contains_both() {
+        let a = BoundingSphere { center: Vector3::zeros(), radius: 1.0 };
+        let b = BoundingSphere { center: Vector3::new(4.0, 0.0, 0.0), radius: 2.0 };
+        let inner = BoundingSphere { center: Vector3::new(0.2, 0.0, 0.0), radius: 0.5 };
+
+        let merged = a.merged(&b);
+        assert!((merged.radius - 3.5).abs() < 1e-5);
+        assert!((merged.center - Vector3::new(2.5, 0.0, 0.0)).norm() < 1e-5);
+        assert_eq!(a.merged(&inner), a);
+        assert_eq!(inner.merged(&a), a);
+    }
+}
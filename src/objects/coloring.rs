@@ -0,0 +1,108 @@
+use std::{fmt, str::FromStr};
+
+use derive_more::Constructor;
+
+use crate::{drawing::colormap::Colormap, expression::Expression, objects::surfaces::SurfaceParam};
+
+//champ scalaire évalué en chaque sommet de la grille (u, v) d'une surface
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScalarField {
+    Gaussian,
+    Mean,
+    K1,
+    K2,
+    U,
+    V,
+    Height, //z dans le repère de l'objet
+    Expression(Expression), //fonction de x, y, z, u et v : "expr:x^2 + y^2"
+}
+
+impl ScalarField {
+    //None là où la grandeur n'est pas définie (courbures en un point dégénéré)
+    pub fn evaluate(&self, param: &SurfaceParam, u: f32, v: f32) -> Option<f32> {
+        match self {
+            ScalarField::Gaussian => param.gaussian_curvature(u, v),
+            ScalarField::Mean => param.mean_curvature(u, v),
+            ScalarField::K1 => param.principal_curvatures(u, v).map(|p| p.k1),
+            ScalarField::K2 => param.principal_curvatures(u, v).map(|p| p.k2),
+            ScalarField::U => Some(u),
+            ScalarField::V => Some(v),
+            ScalarField::Height => Some(param.point(u, v).z),
+            ScalarField::Expression(expression) => {
+                let p = param.point(u, v);
+                Some(expression.eval(&[p.x, p.y, p.z, u, v]))
+            }
+        }
+        .filter(|value| value.is_finite())
+    }
+}
+
+impl FromStr for ScalarField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if let Some(source) = s.trim().strip_prefix("expr:") {
+            return Expression::compile(source, &["x", "y", "z", "u", "v"], &[]).map(ScalarField::Expression);
+        }
+
+        match s.trim() {
+            "gaussian" => Ok(ScalarField::Gaussian),
+            "mean" => Ok(ScalarField::Mean),
+            "k1" => Ok(ScalarField::K1),
+            "k2" => Ok(ScalarField::K2),
+            "u" => Ok(ScalarField::U),
+            "v" => Ok(ScalarField::V),
+            "height" => Ok(ScalarField::Height),
+            other => Err(anyhow::anyhow!("Champ '{}' inconnu (gaussian, mean, k1, k2, u, v, height ou expr:f(x,y,z,u,v))", other)),
+        }
+    }
+}
+
+impl fmt::Display for ScalarField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalarField::Gaussian => write!(f, "gaussian"),
+            ScalarField::Mean => write!(f, "mean"),
+            ScalarField::K1 => write!(f, "k1"),
+            ScalarField::K2 => write!(f, "k2"),
+            ScalarField::U => write!(f, "u"),
+            ScalarField::V => write!(f, "v"),
+            ScalarField::Height => write!(f, "height"),
+            ScalarField::Expression(expression) => write!(f, "expr:{}", expression.source()),
+        }
+    }
+}
+
+//coloration d'une surface par un champ scalaire, l'intervalle est calculé sur la surface s'il n'est pas donné
+#[derive(Constructor, Clone, Debug, PartialEq)]
+pub struct SurfaceColoring {
+    pub field: ScalarField,
+    pub colormap: Colormap,
+    pub range: Option<(f32, f32)>,
+}
+
+impl SurfaceColoring {
+    //les valeurs extrêmes (1 % de chaque côté) sont ignorées, pour que quelques points presque dégénérés n'écrasent pas la palette
+    //une palette divergente reçoit un intervalle symétrique autour de zéro
+    pub fn resolve_range(&self, values: &[f32]) -> (f32, f32) {
+        if let Some(range) = self.range {
+            return range;
+        }
+
+        let mut sorted: Vec<f32> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        if sorted.is_empty() {
+            return (0.0, 1.0);
+        }
+        sorted.sort_by(f32::total_cmp);
+
+        let last = (sorted.len() - 1) as f32;
+        let (min, max) = (sorted[(0.01 * last).floor() as usize], sorted[(0.99 * last).ceil() as usize]);
+
+        if self.colormap.is_diverging() {
+            let bound = min.abs().max(max.abs());
+            (-bound, bound)
+        } else {
+            (min, max)
+        }
+    }
+}
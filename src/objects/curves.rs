@@ -0,0 +1,387 @@
+use std::{f32::consts::{PI, TAU}, fmt, str::FromStr};
+
+use derive_more::Constructor;
+use itertools::Itertools;
+use nalgebra::Vector3;
+
+use crate::{expression::Expression, objects::surfaces::{key_values, parse_count, parse_definitions, parse_number, parse_range}};
+
+//variété de dimension 1 : t -> f(t) échantillonnée régulièrement sur t_range
+pub struct Curve {
+    f: Box<dyn Fn(f32) -> Vector3<f32>>,
+    t_range: (f32, f32),
+    points_number: u32,
+}
+
+impl Curve {
+    pub fn new(f: Box<dyn Fn(f32) -> Vector3<f32>>, t_range: (f32, f32), points_number: u32) -> Self {
+        Self { f, t_range, points_number }
+    }
+
+    //noeud torique (p, q) posé sur un tore de rayons r et r_rev
+    pub fn new_torus_knot(p: f32, q: f32, r: f32, r_rev: f32, points_number: u32) -> Self {
+        let f = move |t: f32| {
+            let radius = r + r_rev * (q * t).cos();
+            Vector3::new(radius * (p * t).cos(), radius * (p * t).sin(), r_rev * (q * t).sin())
+        };
+
+        Self::new(Box::new(f), (0.0, TAU), points_number)
+    }
+
+    pub fn new_trefoil(scale: f32, points_number: u32) -> Self {
+        let f = move |t: f32| {
+            let x = t.sin() + 2.0 * (2.0 * t).sin();
+            let y = t.cos() - 2.0 * (2.0 * t).cos();
+            let z = -(3.0 * t).sin();
+
+            scale * Vector3::new(x, y, z)
+        };
+
+        Self::new(Box::new(f), (0.0, TAU), points_number)
+    }
+
+    //intersection de la sphère de rayon 2a centrée en 0 et du cylindre de rayon a passant par l'axe z
+    pub fn new_viviani(a: f32, points_number: u32) -> Self {
+        let f = move |t: f32| Vector3::new(a * (1.0 + t.cos()), a * t.sin(), 2.0 * a * (t / 2.0).sin());
+
+        Self::new(Box::new(f), (-2.0 * PI, 2.0 * PI), points_number)
+    }
+
+    pub fn new_helix(r: f32, pitch: f32, turns: f32, points_number: u32) -> Self {
+        let f = move |t: f32| Vector3::new(r * t.cos(), r * t.sin(), pitch * t / TAU);
+
+        Self::new(Box::new(f), (0.0, TAU * turns), points_number)
+    }
+
+    pub fn point(&self, t: f32) -> Vector3<f32> {
+        (self.f)(t)
+    }
+
+    pub fn t_range(&self) -> (f32, f32) {
+        self.t_range
+    }
+
+    pub fn points_number(&self) -> u32 {
+        self.points_number
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Vector3<f32>> + '_ {
+        let (a, b) = self.t_range;
+        let n = self.points_number.max(2);
+
+        (0..n).map(move |i| (self.f)(a + (b - a) * i as f32 / (n - 1) as f32))
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> + '_ {
+        self.points().tuple_windows()
+    }
+
+    fn step(&self) -> f32 {
+        (self.t_range.1 - self.t_range.0) * 1e-3
+    }
+
+    //tangente unitaire en t par differences finies centrées (nulle si la courbe est stationnaire en ce point)
+    pub fn tangent(&self, t: f32) -> Vector3<f32> {
+        let h = self.step();
+        ((self.f)(t + h) - (self.f)(t - h)).try_normalize(f32::EPSILON).unwrap_or_else(Vector3::zeros)
+    }
+
+    //courbe fermée si ses extrémités sont confondues (à la précision de l'échantillonnage près)
+    pub fn is_closed(&self) -> bool {
+        let (a, b) = self.t_range;
+        let length_scale = self.segments().map(|(p0, p1)| (p1 - p0).norm()).fold(0.0, f32::max);
+
+        ((self.f)(a) - (self.f)(b)).norm() <= 1e-3 * length_scale.max(f32::EPSILON)
+    }
+
+    //repère de Frenet, la normale pointe vers le centre de courbure
+    //sur une portion droite (courbure nulle) elle est remplacée par une direction orthogonale quelconque
+    pub fn frenet_frame(&self, t: f32) -> Frame {
+        let h = self.step();
+        let tangent = self.tangent(t);
+        let acceleration = (self.f)(t + h) - 2.0 * (self.f)(t) + (self.f)(t - h);
+        let normal = (acceleration - acceleration.dot(&tangent) * tangent)
+            .try_normalize(1e-6 * h * h)
+            .unwrap_or_else(|| any_orthogonal(&tangent));
+
+        Frame::new(tangent, normal, tangent.cross(&normal))
+    }
+
+    //repère de rotation minimale aux points échantillonnés, par la méthode de double réflexion (Wang et al. 2008)
+    //pour une courbe fermée, le défaut de recollement (holonomie) est réparti le long de la courbe
+    pub fn rotation_minimizing_frames(&self) -> Vec<Frame> {
+        let (a, b) = self.t_range;
+        let n = self.points_number.max(2);
+        let ts: Vec<f32> = (0..n).map(|i| a + (b - a) * i as f32 / (n - 1) as f32).collect();
+        let points: Vec<Vector3<f32>> = ts.iter().map(|&t| (self.f)(t)).collect();
+        let tangents: Vec<Vector3<f32>> = ts.iter().map(|&t| self.tangent(t)).collect();
+
+        let mut normals = vec![self.frenet_frame(a).normal];
+        for i in 0..ts.len() - 1 {
+            let reflect = |v: Vector3<f32>, axis: Vector3<f32>| {
+                let c = axis.norm_squared();
+                if c > f32::EPSILON { v - (2.0 / c) * axis.dot(&v) * axis } else { v }
+            };
+
+            let v1 = points[i + 1] - points[i];
+            let normal = reflect(normals[i], v1);
+            let tangent = reflect(tangents[i], v1);
+            let normal = reflect(normal, tangents[i + 1] - tangent);
+
+            normals.push(normal);
+        }
+
+        let correction = if self.is_closed() {
+            let (first, last) = (normals[0], normals[normals.len() - 1]);
+            let tangent = tangents[0];
+            tangent.cross(&last).dot(&first).atan2(last.dot(&first))
+        } else {
+            0.0
+        };
+
+        tangents
+            .iter()
+            .zip(normals)
+            .enumerate()
+            .map(|(i, (&tangent, normal))| {
+                let angle = correction * i as f32 / (n - 1) as f32;
+                let normal = normal * angle.cos() + tangent.cross(&normal) * angle.sin();
+                Frame::orthonormalized(tangent, normal)
+            })
+            .collect()
+    }
+
+    //position et repère en tout t, la courbe est consommée pour pouvoir construire une surface autour d'elle
+    //le repère de rotation minimale n'est connu qu'aux points échantillonnés, il est interpolé entre eux
+    pub fn into_moving_frame(self, kind: FrameKind) -> Box<dyn Fn(f32) -> (Vector3<f32>, Frame)> {
+        match kind {
+            FrameKind::Frenet => Box::new(move |t| (self.point(t), self.frenet_frame(t))),
+            FrameKind::RotationMinimizing => {
+                let frames = self.rotation_minimizing_frames();
+                let (a, b) = self.t_range;
+
+                Box::new(move |t| {
+                    let x = ((t - a) / (b - a)).clamp(0.0, 1.0) * (frames.len() - 1) as f32;
+                    let i = (x.floor() as usize).min(frames.len() - 2);
+                    let normal = frames[i].normal.lerp(&frames[i + 1].normal, x - i as f32);
+
+                    (self.point(t), Frame::orthonormalized(self.tangent(t), normal))
+                })
+            }
+        }
+    }
+}
+
+#[derive(Constructor, Clone, Copy, Debug)]
+pub struct Frame {
+    pub tangent: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub binormal: Vector3<f32>,
+}
+
+impl Frame {
+    //repère direct à partir de la tangente et d'une normale approchée
+    fn orthonormalized(tangent: Vector3<f32>, normal: Vector3<f32>) -> Self {
+        let normal = (normal - normal.dot(&tangent) * tangent)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| any_orthogonal(&tangent));
+
+        Self::new(tangent, normal, tangent.cross(&normal))
+    }
+}
+
+fn any_orthogonal(v: &Vector3<f32>) -> Vector3<f32> {
+    let axis = if v.x.abs() < 0.9 { Vector3::x() } else { Vector3::y() };
+    v.cross(&axis).try_normalize(f32::EPSILON).unwrap_or_else(Vector3::z)
+}
+
+//repère mobile utilisé pour balayer un profil le long d'une courbe :
+//Frenet suit la courbure (et se retourne aux points d'inflexion), la rotation minimale ne tourne pas autour de la tangente
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameKind {
+    #[default]
+    RotationMinimizing,
+    Frenet,
+}
+
+impl FromStr for FrameKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim() {
+            "rmf" => Ok(FrameKind::RotationMinimizing),
+            "frenet" => Ok(FrameKind::Frenet),
+            other => Err(anyhow::anyhow!("Repère '{}' inconnu (rmf ou frenet)", other)),
+        }
+    }
+}
+
+impl fmt::Display for FrameKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameKind::RotationMinimizing => write!(f, "rmf"),
+            FrameKind::Frenet => write!(f, "frenet"),
+        }
+    }
+}
+
+//description textuelle d'une courbe, sur le modèle de SurfaceSpec : "torus_knot:p=2,q=3,r=10,r_rev=4,n=400"
+//ou par des expressions de t : "expr:x=cos(t); y=sin(t); z=t/5; t_range=0..6pi; n=300"
+#[derive(Clone, Debug, PartialEq)]
+pub enum CurveSpec {
+    TorusKnot { p: f32, q: f32, r: f32, r_rev: f32, n: u32 },
+    Trefoil { scale: f32, n: u32 },
+    Viviani { a: f32, n: u32 },
+    Helix { r: f32, pitch: f32, turns: f32, n: u32 },
+    Expression(ExpressionCurve),
+}
+
+impl CurveSpec {
+    pub fn build(&self) -> Curve {
+        match *self {
+            CurveSpec::TorusKnot { p, q, r, r_rev, n } => Curve::new_torus_knot(p, q, r, r_rev, n),
+            CurveSpec::Trefoil { scale, n } => Curve::new_trefoil(scale, n),
+            CurveSpec::Viviani { a, n } => Curve::new_viviani(a, n),
+            CurveSpec::Helix { r, pitch, turns, n } => Curve::new_helix(r, pitch, turns, n),
+            CurveSpec::Expression(ref curve) => curve.build(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            CurveSpec::TorusKnot { .. } => "torus_knot",
+            CurveSpec::Trefoil { .. } => "trefoil",
+            CurveSpec::Viviani { .. } => "viviani",
+            CurveSpec::Helix { .. } => "helix",
+            CurveSpec::Expression(_) => "expr",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, f32)> {
+        match *self {
+            CurveSpec::TorusKnot { p, q, r, r_rev, n } => vec![("p", p), ("q", q), ("r", r), ("r_rev", r_rev), ("n", n as f32)],
+            CurveSpec::Trefoil { scale, n } => vec![("scale", scale), ("n", n as f32)],
+            CurveSpec::Viviani { a, n } => vec![("a", a), ("n", n as f32)],
+            CurveSpec::Helix { r, pitch, turns, n } => vec![("r", r), ("pitch", pitch), ("turns", turns), ("n", n as f32)],
+            CurveSpec::Expression(_) => vec![],
+        }
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        let number = || parse_number(key, value);
+
+        match (self, key) {
+            (CurveSpec::TorusKnot { p, .. }, "p") => *p = number()?,
+            (CurveSpec::TorusKnot { q, .. }, "q") => *q = number()?,
+            (CurveSpec::TorusKnot { r, .. } | CurveSpec::Helix { r, .. }, "r") => *r = number()?,
+            (CurveSpec::TorusKnot { r_rev, .. }, "r_rev") => *r_rev = number()?,
+            (CurveSpec::Trefoil { scale, .. }, "scale") => *scale = number()?,
+            (CurveSpec::Viviani { a, .. }, "a") => *a = number()?,
+            (CurveSpec::Helix { pitch, .. }, "pitch") => *pitch = number()?,
+            (CurveSpec::Helix { turns, .. }, "turns") => *turns = number()?,
+            (CurveSpec::TorusKnot { n, .. } | CurveSpec::Trefoil { n, .. } | CurveSpec::Viviani { n, .. } | CurveSpec::Helix { n, .. }, "n") => *n = parse_count(key, value, 2)?,
+            (spec, _) => return Err(anyhow::anyhow!("Paramètre '{}' inconnu pour la courbe '{}'", key, spec.name())),
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for CurveSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (name, params) = s.split_once(':').unwrap_or((s, ""));
+
+        if name.trim() == "expr" {
+            return params.parse().map(CurveSpec::Expression);
+        }
+
+        let mut spec = match name.trim() {
+            "torus_knot" => CurveSpec::TorusKnot { p: 2.0, q: 3.0, r: 10.0, r_rev: 4.0, n: 400 },
+            "trefoil" => CurveSpec::Trefoil { scale: 3.0, n: 300 },
+            "viviani" => CurveSpec::Viviani { a: 5.0, n: 300 },
+            "helix" => CurveSpec::Helix { r: 5.0, pitch: 4.0, turns: 5.0, n: 300 },
+            other => return Err(anyhow::anyhow!("Courbe '{}' inconnue (torus_knot, trefoil, viviani, helix ou expr)", other)),
+        };
+
+        for (key, value) in key_values(params, ',')? {
+            spec.set_param(key, value)?;
+        }
+
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for CurveSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let CurveSpec::Expression(curve) = self {
+            return write!(f, "expr:{curve}");
+        }
+
+        let params = self.params().iter().map(|(k, v)| format!("{k}={v}")).join(",");
+        write!(f, "{}:{}", self.name(), params)
+    }
+}
+
+//courbe définie par des expressions de t, définitions séparées par des ';' comme pour ExpressionSurface
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpressionCurve {
+    definitions: Vec<(String, String)>, //telles qu'écrites, pour l'affichage
+    x: Expression,
+    y: Expression,
+    z: Expression,
+    t_range: (f32, f32),
+    n: u32,
+}
+
+impl ExpressionCurve {
+    pub fn build(&self) -> Curve {
+        let (x, y, z) = (self.x.clone(), self.y.clone(), self.z.clone());
+        let f = move |t: f32| Vector3::new(x.eval(&[t]), y.eval(&[t]), z.eval(&[t]));
+
+        Curve::new(Box::new(f), self.t_range, self.n)
+    }
+}
+
+impl FromStr for ExpressionCurve {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (mut x, mut y, mut z) = (None, None, None);
+        let mut t_range = (0.0, 1.0);
+        let mut n = 200;
+
+        let definitions = parse_definitions(s, |key, value, constants| {
+            let expression = || Expression::compile(value, &["t"], constants);
+
+            match key {
+                "x" => x = Some(expression()?),
+                "y" => y = Some(expression()?),
+                "z" => z = Some(expression()?),
+                "t_range" => t_range = parse_range(value, constants)?,
+                "n" => n = parse_count(key, value, 2)?,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        let missing = |name: &str| anyhow::anyhow!("'{}' manquant dans la courbe", name);
+
+        Ok(Self {
+            x: x.ok_or_else(|| missing("x"))?,
+            y: y.ok_or_else(|| missing("y"))?,
+            z: z.ok_or_else(|| missing("z"))?,
+            definitions,
+            t_range,
+            n,
+        })
+    }
+}
+
+impl fmt::Display for ExpressionCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let definitions = self.definitions.iter().map(|(k, v)| format!("{k}={v}")).join("; ");
+        write!(f, "{definitions}")
+    }
+}
@@ -0,0 +1,205 @@
+use derive_more::Constructor;
+use nalgebra::Vector3;
+
+//dérivées partielles premières et secondes d'un paramètrage f(u, v) en un point
+#[derive(Constructor, Clone, Copy, Debug)]
+pub struct Partials {
+    pub fu: Vector3<f32>,
+    pub fv: Vector3<f32>,
+    pub fuu: Vector3<f32>,
+    pub fuv: Vector3<f32>,
+    pub fvv: Vector3<f32>,
+}
+
+//forme quadratique sur le plan tangent dans la base (fu, fv) : uu du² + 2 uv du dv + vv dv²
+//(E, F, G) pour la première forme fondamentale, (L, M, N) pour la seconde
+#[derive(Constructor, Clone, Copy, Debug, PartialEq)]
+pub struct QuadraticForm {
+    pub uu: f32,
+    pub uv: f32,
+    pub vv: f32,
+}
+
+impl QuadraticForm {
+    pub fn determinant(&self) -> f32 {
+        self.uu * self.vv - self.uv * self.uv
+    }
+}
+
+//courbures principales k1 >= k2 et directions principales associées (unitaires, dans l'espace)
+#[derive(Clone, Copy, Debug)]
+pub struct PrincipalCurvatures {
+    pub k1: f32,
+    pub k2: f32,
+    pub d1: Vector3<f32>,
+    pub d2: Vector3<f32>,
+}
+
+//toutes les grandeurs sont relatives à la normale fu x fv, leur signe dépend donc de l'orientation du paramètrage
+//elles ne sont pas définies (None) là où le paramètrage est dégénéré (fu et fv colinéaires)
+impl Partials {
+    pub fn normal(&self) -> Option<Vector3<f32>> {
+        self.fu.cross(&self.fv).try_normalize(f32::EPSILON)
+    }
+
+    pub fn first_fundamental_form(&self) -> QuadraticForm {
+        QuadraticForm::new(self.fu.dot(&self.fu), self.fu.dot(&self.fv), self.fv.dot(&self.fv))
+    }
+
+    pub fn second_fundamental_form(&self) -> Option<QuadraticForm> {
+        let n = self.normal()?;
+        Some(QuadraticForm::new(self.fuu.dot(&n), self.fuv.dot(&n), self.fvv.dot(&n)))
+    }
+
+    //symboles de Christoffel [Γ^u, Γ^v], Γ^k_ij = g^kl <f_ij, f_l> : les composantes de f_ij sur la base (fu, fv)
+    pub fn christoffel_symbols(&self) -> Option<[QuadraticForm; 2]> {
+        let first = self.first_fundamental_form();
+        let det = first.determinant();
+        if det <= f32::EPSILON * first.uu.max(first.vv).powi(2) {
+            return None;
+        }
+
+        //coordonnées dans la base (fu, fv) d'un vecteur connu par ses produits scalaires avec fu et fv
+        let components = |w: Vector3<f32>| {
+            let (a, b) = (w.dot(&self.fu), w.dot(&self.fv));
+            ((first.vv * a - first.uv * b) / det, (first.uu * b - first.uv * a) / det)
+        };
+
+        let (uu, uv, vv) = (components(self.fuu), components(self.fuv), components(self.fvv));
+        Some([QuadraticForm::new(uu.0, uv.0, vv.0), QuadraticForm::new(uu.1, uv.1, vv.1)])
+    }
+
+    //formes (I, II) si le paramètrage est régulier en ce point
+    fn forms(&self) -> Option<(QuadraticForm, QuadraticForm)> {
+        let first = self.first_fundamental_form();
+        let second = self.second_fundamental_form()?;
+
+        (first.determinant() > f32::EPSILON * first.uu.max(first.vv).powi(2)).then_some((first, second))
+    }
+
+    //(K, H) avec K = (LN - M²) / (EG - F²) et H = (EN - 2FM + GL) / 2(EG - F²)
+    fn curvatures(first: &QuadraticForm, second: &QuadraticForm) -> (f32, f32) {
+        let k = second.determinant() / first.determinant();
+        let h = (first.uu * second.vv - 2.0 * first.uv * second.uv + first.vv * second.uu) / (2.0 * first.determinant());
+
+        (k, h)
+    }
+
+    pub fn gaussian_curvature(&self) -> Option<f32> {
+        self.forms().map(|(first, second)| Self::curvatures(&first, &second).0)
+    }
+
+    pub fn mean_curvature(&self) -> Option<f32> {
+        self.forms().map(|(first, second)| Self::curvatures(&first, &second).1)
+    }
+
+    //valeurs propres de l'opérateur de forme S = I⁻¹ II : k = H ± √(H² - K)
+    //H² - K est calculé à partir des coefficients de S pour éviter une soustraction catastrophique aux points ombilics
+    //la direction associée à k1 annule (II - k1 I), d2 lui est orthogonale dans le plan tangent
+    pub fn principal_curvatures(&self) -> Option<PrincipalCurvatures> {
+        let (first, second) = self.forms()?;
+        let (_, h) = Self::curvatures(&first, &second);
+
+        let det = first.determinant();
+        let s_uu = (first.vv * second.uu - first.uv * second.uv) / det;
+        let s_uv = (first.vv * second.uv - first.uv * second.vv) / det;
+        let s_vu = (first.uu * second.uv - first.uv * second.uu) / det;
+        let s_vv = (first.uu * second.vv - first.uv * second.uv) / det;
+        let delta = (0.25 * (s_uu - s_vv).powi(2) + s_uv * s_vu).max(0.0).sqrt();
+        let (k1, k2) = (h + delta, h - delta);
+
+        //lignes de la matrice (II - k1 I), on garde la mieux conditionnée
+        let row_u = (second.uu - k1 * first.uu, second.uv - k1 * first.uv);
+        let row_v = (second.uv - k1 * first.uv, second.vv - k1 * first.vv);
+        let (a, b) = if row_u.0.hypot(row_u.1) >= row_v.0.hypot(row_v.1) { row_u } else { row_v };
+
+        let n = self.normal()?;
+        let d1 = (-b * self.fu + a * self.fv)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| self.fu.normalize()); //point ombilic, toutes les directions sont principales
+        let d2 = n.cross(&d1);
+
+        Some(PrincipalCurvatures { k1, k2, d1, d2 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::objects::surfaces::SurfaceParam;
+
+    fn close(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance * b.abs().max(1.0)
+    }
+
+    //même paramètrage, sans les dérivées analytiques : elles sont alors calculées par differences finies
+    fn numeric(param: SurfaceParam) -> SurfaceParam {
+        let (u_range, v_range) = (param.u_range(), param.v_range());
+        SurfaceParam::new(Box::new(move |u, v| param.point(u, v)), u_range, v_range)
+    }
+
+    #[test]
+    fn sphere() {
+        for r in [0.5, 1.0, 10.0] {
+            let sphere = SurfaceParam::new_sphere(r);
+            for (u, v) in [(0.0, 0.0), (1.0, 0.5), (-2.5, -1.2)] {
+                let partials = sphere.partials(u, v);
+                let k = partials.gaussian_curvature().unwrap();
+                let h = partials.mean_curvature().unwrap();
+                let principal = partials.principal_curvatures().unwrap();
+
+                assert!(close(k, 1.0 / (r * r), 1e-4), "K = {k} pour r = {r}");
+                assert!(close(h.abs(), 1.0 / r, 1e-4), "H = {h} pour r = {r}");
+                assert!(close(principal.k1, h, 1e-4) && close(principal.k2, h, 1e-4));
+            }
+        }
+    }
+
+    #[test]
+    fn plane() {
+        let plane = SurfaceParam::new(Box::new(|u, v| Vector3::new(u, v, 2.0 * u - 3.0 * v + 1.0)), (-5.0, 5.0), (-5.0, 5.0));
+        for (u, v) in [(0.0, 0.0), (1.0, -2.0), (4.0, 3.0)] {
+            let partials = plane.partials(u, v);
+            assert!(partials.gaussian_curvature().unwrap().abs() < 1e-3);
+            assert!(partials.mean_curvature().unwrap().abs() < 1e-3);
+        }
+    }
+
+    //K = cos(v) / (r_rev (r + r_rev cos(v))) : positive sur l'équateur extérieur (v = 0), négative sur l'intérieur (v = π)
+    #[test]
+    fn torus_curvature_changes_sign() {
+        let (r, r_rev) = (3.0, 1.0);
+        let torus = SurfaceParam::new_torus(r, r_rev);
+
+        let outer = torus.partials(0.7, 0.0).gaussian_curvature().unwrap();
+        let inner = torus.partials(0.7, PI).gaussian_curvature().unwrap();
+        let top = torus.partials(0.7, PI / 2.0).gaussian_curvature().unwrap();
+
+        assert!(close(outer, 1.0 / (r_rev * (r + r_rev)), 1e-4), "K = {outer} à l'extérieur");
+        assert!(close(inner, -1.0 / (r_rev * (r - r_rev)), 1e-4), "K = {inner} à l'intérieur");
+        assert!(top.abs() < 1e-4, "K = {top} en haut");
+    }
+
+    #[test]
+    fn analytic_partials_match_finite_differences() {
+        let surfaces = [
+            (SurfaceParam::new_sphere(10.0), numeric(SurfaceParam::new_sphere(10.0)), 10.0),
+            (SurfaceParam::new_torus(30.0, 10.0), numeric(SurfaceParam::new_torus(30.0, 10.0)), 40.0),
+        ];
+
+        for (analytic, numeric, scale) in surfaces {
+            let (u_range, v_range) = (analytic.u_range(), analytic.v_range());
+            for (s, t) in [(0.3, 0.2), (0.6, 0.7), (0.8, 0.4)] {
+                let (u, v) = (u_range.0 + s * (u_range.1 - u_range.0), v_range.0 + t * (v_range.1 - v_range.0));
+                let (exact, approx) = (analytic.partials(u, v), numeric.partials(u, v));
+                let pairs = [(exact.fu, approx.fu), (exact.fv, approx.fv), (exact.fuu, approx.fuu), (exact.fuv, approx.fuv), (exact.fvv, approx.fvv)];
+
+                for (i, (a, b)) in pairs.iter().enumerate() {
+                    assert!((a - b).norm() <= 1e-2 * scale, "dérivée {i} en ({u}, {v}) : {b} au lieu de {a}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{f32::consts::TAU, fmt, str::FromStr};
+
+use nalgebra::Vector3;
+
+use crate::objects::{bounds::BoundingBox, differential::QuadraticForm, surfaces::{SurfaceParam, SurfaceSpec, key_values, parse_count, parse_number}};
+
+//état de l'intégration : position (u, v) et vitesse (u', v') par rapport à l'abscisse curviligne
+type State = [f32; 4];
+
+//u'' = -Γ^u(u', v'), v'' = -Γ^v(u', v') avec Γ^k(a, b) = Γ^k_uu a² + 2 Γ^k_uv a b + Γ^k_vv b²
+fn geodesic_equation(param: &SurfaceParam, [u, v, du, dv]: State) -> Option<State> {
+    let [gamma_u, gamma_v] = param.partials(u, v).christoffel_symbols()?;
+    let apply = |gamma: &QuadraticForm| gamma.uu * du * du + 2.0 * gamma.uv * du * dv + gamma.vv * dv * dv;
+
+    Some([du, dv, -apply(&gamma_u), -apply(&gamma_v)])
+}
+
+fn rk4_step(param: &SurfaceParam, state: State, h: f32) -> Option<State> {
+    let add = |a: State, k: State, t: f32| [0, 1, 2, 3].map(|i| a[i] + t * k[i]);
+
+    let k1 = geodesic_equation(param, state)?;
+    let k2 = geodesic_equation(param, add(state, k1, h / 2.0))?;
+    let k3 = geodesic_equation(param, add(state, k2, h / 2.0))?;
+    let k4 = geodesic_equation(param, add(state, k3, h))?;
+
+    Some([0, 1, 2, 3].map(|i| state[i] + h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i])))
+}
+
+//vitesse unitaire (u', v') dont l'image fait l'angle donné avec fu dans le plan tangent, orienté par la normale fu x fv
+fn initial_velocity(param: &SurfaceParam, u: f32, v: f32, angle: f32) -> Option<(f32, f32)> {
+    let partials = param.partials(u, v);
+    let normal = partials.normal()?;
+    let e1 = partials.fu.try_normalize(f32::EPSILON)?;
+    let direction = angle.cos() * e1 + angle.sin() * normal.cross(&e1);
+
+    let first = partials.first_fundamental_form();
+    let (a, b) = (direction.dot(&partials.fu), direction.dot(&partials.fv));
+    let det = first.determinant();
+
+    Some(((first.vv * a - first.uv * b) / det, (first.uu * b - first.uv * a) / det))
+}
+
+//le paramètrage se referme sur lui-même dans cette direction (tore, longitude de la sphère)
+fn is_periodic(param: &SurfaceParam, along_u: bool) -> bool {
+    let ((a, b), (c, d)) = if along_u { (param.u_range(), param.v_range()) } else { (param.v_range(), param.u_range()) };
+    let point = |s: f32, t: f32| if along_u { param.point(s, t) } else { param.point(t, s) };
+
+    (0..5).map(|i| c + (d - c) * i as f32 / 4.0).all(|t| {
+        let (p, q) = (point(a, t), point(b, t));
+        (p - q).norm() <= 1e-4 * p.norm().max(q.norm()).max(1.0)
+    })
+}
+
+//ramène x dans [a; b] si la direction est périodique, None s'il en sort sinon
+fn wrap(x: f32, (a, b): (f32, f32), periodic: bool) -> Option<f32> {
+    match periodic {
+        true => Some(a + (x - a).rem_euclid(b - a)),
+        false => (a..=b).contains(&x).then_some(x),
+    }
+}
+
+//géodésique partant de (u, v) dans la direction angle (voir initial_velocity), intégrée par Runge-Kutta 4 sur la longueur donnée
+//le chemin s'arrête au bord du domaine (sauf dans les directions périodiques) ou en un point où le paramètrage dégénère
+pub fn shoot_geodesic(param: &SurfaceParam, start: (f32, f32), angle: f32, length: f32, step: f32) -> Vec<(f32, f32)> {
+    let (periodic_u, periodic_v) = (is_periodic(param, true), is_periodic(param, false));
+    let Some((du, dv)) = initial_velocity(param, start.0, start.1, angle) else {
+        return vec![start];
+    };
+
+    let mut state = [start.0, start.1, du, dv];
+    let mut path = vec![start];
+
+    for _ in 0..(length / step).ceil() as usize {
+        let Some(next) = rk4_step(param, state, step) else { break };
+        let (Some(u), Some(v)) = (wrap(next[0], param.u_range(), periodic_u), wrap(next[1], param.v_range(), periodic_v)) else { break };
+
+        //la vitesse d'une géodésique est constante, on la renormalise pour limiter la dérive numérique
+        let first = param.first_fundamental_form(u, v);
+        let speed = (first.uu * next[2] * next[2] + 2.0 * first.uv * next[2] * next[3] + first.vv * next[3] * next[3]).sqrt();
+        if !speed.is_finite() || speed <= f32::EPSILON {
+            break;
+        }
+
+        state = [u, v, next[2] / speed, next[3] / speed];
+        path.push((u, v));
+    }
+
+    path
+}
+
+//éventail de géodésiques : count directions réparties régulièrement à partir de angle
+pub fn shoot_geodesic_fan(param: &SurfaceParam, start: (f32, f32), angle: f32, count: u32, length: f32, step: f32) -> Vec<Vec<(f32, f32)>> {
+    (0..count)
+        .map(|i| shoot_geodesic(param, start, angle + TAU * i as f32 / count as f32, length, step))
+        .collect()
+}
+
+//nombre total de pas de Runge-Kutta d'un éventail, au delà le tracé prendrait des minutes
+const MAX_STEPS: u32 = 1_000_000;
+
+//décalage des tracés le long de la normale, relatif au rayon de la boîte englobante de la surface
+const LIFT: f32 = 2e-3;
+
+//description textuelle d'une ou plusieurs géodésiques, les paramètres du tir puis la surface après un '|' :
+//  "u=0,v=1,angle=0.5,length=80,fan=12|torus:r=30,r_rev=10"
+//part désigne le morceau de la surface pour celles qui en ont plusieurs (cube_sphere)
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeodesicSpec {
+    surface: SurfaceSpec,
+    part: usize,
+    start: (f32, f32),
+    angle: f32,
+    length: f32,
+    step: f32,
+    fan: u32,
+}
+
+impl GeodesicSpec {
+    //polylignes dans le repère de la surface, légèrement décollées le long de la normale pour ne pas être masquées par ses faces
+    //le point de départ doit être dans le domaine du paramètrage, ramené dans celui-ci dans les directions périodiques
+    pub fn build(&self) -> anyhow::Result<Vec<Vec<Vector3<f32>>>> {
+        let steps = self.fan as f32 * (self.length / self.step).ceil();
+        if steps > MAX_STEPS as f32 {
+            return Err(anyhow::anyhow!(
+                "Trop de pas d'intégration ({} x {} / {}), au plus {} : augmenter 'step' ou réduire 'length' ou 'fan'",
+                self.fan, self.length, self.step, MAX_STEPS
+            ));
+        }
+
+        let surface = self.surface.build();
+        let param = surface
+            .parts()
+            .get(self.part)
+            .ok_or_else(|| anyhow::anyhow!("La surface n'a que {} morceau(x)", surface.parts().len()))?;
+
+        let (u_range, v_range) = (param.u_range(), param.v_range());
+        let start = match (wrap(self.start.0, u_range, is_periodic(param, true)), wrap(self.start.1, v_range, is_periodic(param, false))) {
+            (Some(u), Some(v)) if u.is_finite() && v.is_finite() => (u, v),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Point de départ ({}, {}) hors du domaine [{}; {}] x [{}; {}] de la surface",
+                    self.start.0, self.start.1, u_range.0, u_range.1, v_range.0, v_range.1
+                ));
+            }
+        };
+
+        //le décalage suit la taille de la surface, pas celle du tracé
+        let lift = LIFT * BoundingBox::from_points(surface.points()).diagonal() / 2.0;
+        let paths = shoot_geodesic_fan(param, start, self.angle, self.fan, self.length, self.step)
+            .into_iter()
+            .map(|path| path.into_iter().map(|(u, v)| param.point(u, v) + lift * param.normal(u, v)).collect())
+            .collect();
+
+        Ok(paths)
+    }
+}
+
+impl FromStr for GeodesicSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (params, surface) = s
+            .split_once('|')
+            .ok_or_else(|| anyhow::anyhow!("Surface manquante, 'paramètres|surface' attendu"))?;
+
+        let mut spec = Self { surface: surface.parse()?, part: 0, start: (0.0, 0.0), angle: 0.0, length: 50.0, step: 0.1, fan: 1 };
+
+        for (key, value) in key_values(params, ',')? {
+            let number = || parse_number(key, value);
+            let positive = || number().and_then(|x| (x > 0.0).then_some(x).ok_or_else(|| anyhow::anyhow!("'{}' doit être positif", key)));
+
+            match key {
+                "u" => spec.start.0 = number()?,
+                "v" => spec.start.1 = number()?,
+                "angle" => spec.angle = number()?,
+                "length" => spec.length = positive()?,
+                "step" => spec.step = positive()?,
+                "fan" => spec.fan = parse_count(key, value, 1)?,
+                "part" => spec.part = parse_count(key, value, 0)? as usize,
+                other => return Err(anyhow::anyhow!("Paramètre '{}' inconnu pour une géodésique (u, v, angle, length, step, fan ou part)", other)),
+            }
+        }
+
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for GeodesicSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "u={},v={},angle={},length={},step={},fan={},part={}|{}",
+            self.start.0, self.start.1, self.angle, self.length, self.step, self.fan, self.part, self.surface
+        )
+    }
+}
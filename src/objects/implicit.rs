@@ -0,0 +1,283 @@
+use std::{fmt, str::FromStr};
+
+use itertools::Itertools;
+use nalgebra::Vector3;
+
+use crate::{expression::Expression, objects::{mesh::Mesh, surfaces::{parse_count, parse_definitions, parse_range}}};
+
+//surface de niveau {F(x, y, z) = iso} échantillonnée sur une grille régulière d'une boite
+pub struct ImplicitSurface {
+    f: Box<dyn Fn(Vector3<f32>) -> f32>,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    resolution: u32, //nombre de cellules par axe
+    iso: f32,
+}
+
+impl ImplicitSurface {
+    pub fn new(f: Box<dyn Fn(Vector3<f32>) -> f32>, min: Vector3<f32>, max: Vector3<f32>, resolution: u32, iso: f32) -> Self {
+        Self { f, min, max, resolution: resolution.max(1), iso }
+    }
+
+    pub fn new_gyroid(period: f32, resolution: u32) -> Self {
+        let f = move |p: Vector3<f32>| {
+            let (x, y, z) = (p.x / period, p.y / period, p.z / period);
+            x.sin() * y.cos() + y.sin() * z.cos() + z.sin() * x.cos()
+        };
+        let half = Vector3::repeat(std::f32::consts::PI * period);
+
+        Self::new(Box::new(f), -half, half, resolution, 0.0)
+    }
+
+    //sextique de Barth, 65 points doubles
+    pub fn new_barth_sextic(scale: f32, resolution: u32) -> Self {
+        let phi = (1.0 + 5f32.sqrt()) / 2.0;
+        let f = move |p: Vector3<f32>| {
+            let (x2, y2, z2) = ((p.x / scale).powi(2), (p.y / scale).powi(2), (p.z / scale).powi(2));
+            let phi2 = phi * phi;
+            4.0 * (phi2 * x2 - y2) * (phi2 * y2 - z2) * (phi2 * z2 - x2) - (1.0 + 2.0 * phi) * (x2 + y2 + z2 - 1.0).powi(2)
+        };
+        let half = Vector3::repeat(1.5 * scale);
+
+        Self::new(Box::new(f), -half, half, resolution, 0.0)
+    }
+
+    pub fn new_tanglecube(scale: f32, resolution: u32) -> Self {
+        let f = move |p: Vector3<f32>| {
+            let q = p / scale;
+            q.x.powi(4) - 5.0 * q.x.powi(2) + q.y.powi(4) - 5.0 * q.y.powi(2) + q.z.powi(4) - 5.0 * q.z.powi(2) + 11.8
+        };
+        let half = Vector3::repeat(3.0 * scale);
+
+        Self::new(Box::new(f), -half, half, resolution, 0.0)
+    }
+
+    fn value(&self, p: Vector3<f32>) -> f32 {
+        (self.f)(p) - self.iso
+    }
+
+    fn cell_size(&self) -> Vector3<f32> {
+        (self.max - self.min) / self.resolution as f32
+    }
+
+    //normale par le gradient de F (differences finies centrées)
+    fn normal(&self, p: Vector3<f32>) -> Vector3<f32> {
+        let h = self.cell_size() * 0.25;
+        let gradient = Vector3::new(
+            self.value(p + Vector3::x() * h.x) - self.value(p - Vector3::x() * h.x),
+            self.value(p + Vector3::y() * h.y) - self.value(p - Vector3::y() * h.y),
+            self.value(p + Vector3::z() * h.z) - self.value(p - Vector3::z() * h.z),
+        );
+
+        gradient.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::zeros)
+    }
+
+    //extraction du maillage par dual contouring : un sommet par cellule traversée par la surface, placé au barycentre
+    //des intersections sur ses arêtes, puis un quadrilatère entre les quatre cellules autour de chaque arête de la grille
+    //où F change de signe
+    pub fn polygonize(&self) -> Mesh {
+        let n = self.resolution as usize;
+        let samples = n + 1;
+        let size = self.cell_size();
+
+        let point = |i: usize, j: usize, k: usize| self.min + Vector3::new(i as f32 * size.x, j as f32 * size.y, k as f32 * size.z);
+        let sample_index = |i: usize, j: usize, k: usize| (i * samples + j) * samples + k;
+        let cell_index = |i: usize, j: usize, k: usize| (i * n + j) * n + k;
+
+        let values: Vec<f32> = (0..samples)
+            .cartesian_product(0..samples)
+            .cartesian_product(0..samples)
+            .map(|((i, j), k)| self.value(point(i, j, k)))
+            .collect();
+
+        let corners: [(usize, usize, usize); 8] = [(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0), (0, 0, 1), (1, 0, 1), (0, 1, 1), (1, 1, 1)];
+        let cube_edges: [(usize, usize); 12] = [(0, 1), (2, 3), (4, 5), (6, 7), (0, 2), (1, 3), (4, 6), (5, 7), (0, 4), (1, 5), (2, 6), (3, 7)];
+
+        let mut vertices = Vec::new();
+        let mut cell_vertex = vec![usize::MAX; n * n * n];
+
+        for ((i, j), k) in (0..n).cartesian_product(0..n).cartesian_product(0..n) {
+            let corner_values = corners.map(|(di, dj, dk)| values[sample_index(i + di, j + dj, k + dk)]);
+            let corner_points = corners.map(|(di, dj, dk)| point(i + di, j + dj, k + dk));
+
+            let crossings: Vec<Vector3<f32>> = cube_edges
+                .iter()
+                .filter(|&&(a, b)| (corner_values[a] < 0.0) != (corner_values[b] < 0.0))
+                .map(|&(a, b)| {
+                    let t = corner_values[a] / (corner_values[a] - corner_values[b]);
+                    corner_points[a] + (corner_points[b] - corner_points[a]) * t
+                })
+                .collect();
+
+            if !crossings.is_empty() {
+                cell_vertex[cell_index(i, j, k)] = vertices.len();
+                vertices.push(crossings.iter().sum::<Vector3<f32>>() / crossings.len() as f32);
+            }
+        }
+
+        let mut faces = Vec::new();
+
+        //pour chaque axe, les arêtes intérieures (entourées de quatre cellules) qui changent de signe
+        for axis in 0..3 {
+            let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+
+            for ((i, j), k) in (0..samples).cartesian_product(0..samples).cartesian_product(0..samples) {
+                let start = [i, j, k];
+                if start[axis] >= n || start[u_axis] == 0 || start[v_axis] == 0 || start[u_axis] >= n || start[v_axis] >= n {
+                    continue;
+                }
+
+                let mut end = start;
+                end[axis] += 1;
+
+                let inside = values[sample_index(start[0], start[1], start[2])] < 0.0;
+                if inside == (values[sample_index(end[0], end[1], end[2])] < 0.0) {
+                    continue;
+                }
+
+                let cell = |du: usize, dv: usize| {
+                    let mut c = start;
+                    c[u_axis] -= du;
+                    c[v_axis] -= dv;
+                    cell_vertex[cell_index(c[0], c[1], c[2])]
+                };
+                let quad = [cell(1, 1), cell(0, 1), cell(0, 0), cell(1, 0)];
+
+                //orientation selon le sens de traversée pour garder des faces cohérentes
+                let [a, b, c, d] = if inside { quad } else { [quad[0], quad[3], quad[2], quad[1]] };
+                faces.push([a, b, c]);
+                faces.push([a, c, d]);
+            }
+        }
+
+        let normals = vertices.iter().map(|&p| self.normal(p)).collect();
+        Mesh::new_with_normals(vertices, normals, faces)
+    }
+}
+
+//surface implicite décrite par du texte, comme ExpressionSurface : définitions séparées par des ';'
+//  "f=x^2+y^2+z^2-1; bounds=-1.5..1.5; n=40; iso=0"
+//x_range, y_range et z_range précisent la boite axe par axe, tout autre nom définit une constante
+//un nom prédéfini en tête (gyroid, barth, tanglecube) donne une surface connue dont on peut changer n, scale et iso, mais pas la boite :
+//  "gyroid; scale=5; n=80"
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImplicitSpec {
+    definitions: Vec<(String, String)>, //telles qu'écrites, pour l'affichage
+    shape: ImplicitShape,
+    ranges: [(f32, f32); 3],
+    resolution: u32,
+    iso: f32,
+    scale: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ImplicitShape {
+    Gyroid,
+    Barth,
+    Tanglecube,
+    Expression(Expression),
+}
+
+impl ImplicitSpec {
+    pub fn build(&self) -> ImplicitSurface {
+        let mut surface = match &self.shape {
+            ImplicitShape::Gyroid => ImplicitSurface::new_gyroid(self.scale, self.resolution),
+            ImplicitShape::Barth => ImplicitSurface::new_barth_sextic(self.scale, self.resolution),
+            ImplicitShape::Tanglecube => ImplicitSurface::new_tanglecube(self.scale, self.resolution),
+            ImplicitShape::Expression(f) => {
+                let f = f.clone();
+                let [x, y, z] = self.ranges;
+                ImplicitSurface::new(
+                    Box::new(move |p: Vector3<f32>| f.eval(&[p.x, p.y, p.z])),
+                    Vector3::new(x.0, y.0, z.0),
+                    Vector3::new(x.1, y.1, z.1),
+                    self.resolution,
+                    0.0,
+                )
+            }
+        };
+        surface.iso = self.iso;
+
+        surface
+    }
+}
+
+impl FromStr for ImplicitSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        //une surface prédéfinie est nommée en tête, avant les définitions
+        let (first, rest) = s.trim_start().split_once(';').unwrap_or((s.trim_start(), ""));
+        let (preset, definitions) = match first.trim() {
+            first if !first.is_empty() && !first.contains('=') => {
+                let shape = match first {
+                    "gyroid" => ImplicitShape::Gyroid,
+                    "barth" => ImplicitShape::Barth,
+                    "tanglecube" => ImplicitShape::Tanglecube,
+                    other => return Err(anyhow::anyhow!("Surface implicite '{}' inconnue (gyroid, barth ou tanglecube)", other)),
+                };
+                (Some(shape), rest)
+            }
+            _ => (None, s),
+        };
+
+        //la boite ne sert qu'à 'f', l'échelle qu'aux surfaces prédéfinies : les donner à l'autre est une erreur plutôt qu'un réglage ignoré
+        let misplaced = match preset {
+            Some(_) => ["bounds", "x_range", "y_range", "z_range"].as_slice(),
+            None => ["scale"].as_slice(),
+        };
+
+        let mut f = None;
+        let mut ranges = [(-1.0, 1.0); 3];
+        let mut resolution = 40;
+        let mut iso = 0.0;
+        let mut scale = 1.0;
+
+        let definitions = parse_definitions(definitions, |key, value, constants| {
+            if misplaced.contains(&key) {
+                return Err(match preset {
+                    Some(_) => anyhow::anyhow!("'{}' ne s'applique pas à une surface prédéfinie, dont la taille se règle par 'scale'", key),
+                    None => anyhow::anyhow!("'scale' ne s'applique qu'aux surfaces prédéfinies, la boite de 'f' se règle par 'bounds' ou 'x_range', 'y_range', 'z_range'"),
+                });
+            }
+
+            match key {
+                "f" => f = Some(Expression::compile(value, &["x", "y", "z"], constants)?),
+                "bounds" => ranges = [parse_range(value, constants)?; 3],
+                "x_range" => ranges[0] = parse_range(value, constants)?,
+                "y_range" => ranges[1] = parse_range(value, constants)?,
+                "z_range" => ranges[2] = parse_range(value, constants)?,
+                "n" => resolution = parse_count(key, value, 1)?,
+                "iso" => iso = Expression::evaluate_constant(value, constants)?,
+                "scale" => scale = Expression::evaluate_constant(value, constants)?,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        let shape = match (preset, f) {
+            (Some(_), Some(_)) => return Err(anyhow::anyhow!("'f' ne peut pas être donnée avec une surface prédéfinie")),
+            (Some(shape), None) => shape,
+            (None, Some(f)) => ImplicitShape::Expression(f),
+            (None, None) => return Err(anyhow::anyhow!("'f' manquant dans la surface implicite")),
+        };
+
+        Ok(Self { definitions, shape, ranges, resolution, iso, scale })
+    }
+}
+
+impl fmt::Display for ImplicitSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let preset = match self.shape {
+            ImplicitShape::Gyroid => Some("gyroid".to_string()),
+            ImplicitShape::Barth => Some("barth".to_string()),
+            ImplicitShape::Tanglecube => Some("tanglecube".to_string()),
+            ImplicitShape::Expression(_) => None,
+        };
+        let definitions = preset
+            .into_iter()
+            .chain(self.definitions.iter().map(|(k, v)| format!("{k}={v}")))
+            .join("; ");
+        write!(f, "{definitions}")
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use nalgebra::Vector3;
+
+//maillage triangulaire indexé, avec une normale par sommet
+pub struct Mesh {
+    vertices: Vec<Vector3<f32>>,
+    normals: Vec<Vector3<f32>>,
+    faces: Vec<[usize; 3]>,
+    edges: Vec<[usize; 2]>, //arêtes uniques, pour que le rendu filaire ne dessine chaque arête qu'une fois
+    uvs: Option<Vec<(f32, f32)>>, //coordonnées de texture, les paramètres (u, v) pour un maillage issu d'une surface
+    colors: Option<Vec<u32>>,
+}
+
+impl Mesh {
+    //les normales aux sommets sont la moyenne des normales des faces adjacentes, pondérée par leur aire
+    pub fn new(vertices: Vec<Vector3<f32>>, faces: Vec<[usize; 3]>) -> Self {
+        let mut normals = vec![Vector3::zeros(); vertices.len()];
+
+        for &[a, b, c] in &faces {
+            let face_normal = (vertices[b] - vertices[a]).cross(&(vertices[c] - vertices[a]));
+            for i in [a, b, c] {
+                normals[i] += face_normal;
+            }
+        }
+
+        let normals = normals
+            .into_iter()
+            .map(|n| n.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::zeros))
+            .collect();
+
+        Self::new_with_normals(vertices, normals, faces)
+    }
+
+    pub fn new_with_normals(vertices: Vec<Vector3<f32>>, normals: Vec<Vector3<f32>>, faces: Vec<[usize; 3]>) -> Self {
+        let mut seen = HashSet::new();
+        let edges = faces
+            .iter()
+            .flat_map(|&[a, b, c]| [[a, b], [b, c], [c, a]])
+            .map(|[a, b]| [a.min(b), a.max(b)])
+            .filter(|edge| seen.insert(*edge))
+            .collect();
+
+        Self { vertices, normals, faces, edges, uvs: None, colors: None }
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<(f32, f32)>) -> Self {
+        assert_eq!(uvs.len(), self.vertices.len(), "Une coordonnée de texture par sommet");
+        self.uvs = Some(uvs);
+        self
+    }
+
+    pub fn with_colors(mut self, colors: Vec<u32>) -> Self {
+        assert_eq!(colors.len(), self.vertices.len(), "Une couleur par sommet");
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn vertices(&self) -> &[Vector3<f32>] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Vector3<f32>] {
+        &self.normals
+    }
+
+    pub fn faces(&self) -> &[[usize; 3]] {
+        &self.faces
+    }
+
+    pub fn uvs(&self) -> Option<&[(f32, f32)]> {
+        self.uvs.as_deref()
+    }
+
+    pub fn colors(&self) -> Option<&[u32]> {
+        self.colors.as_deref()
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> + '_ {
+        self.edges.iter().map(|&[a, b]| (self.vertices[a], self.vertices[b]))
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = ([Vector3<f32>; 3], [Vector3<f32>; 3])> + '_ {
+        self.faces.iter().map(|face| (face.map(|i| self.vertices[i]), face.map(|i| self.normals[i])))
+    }
+
+    //couleur du sommet, nulle (celle de l'objet) si le maillage n'est pas coloré
+    fn color(&self, i: usize) -> u32 {
+        self.colors.as_ref().map_or(0, |colors| colors[i])
+    }
+
+    pub fn colored_edges(&self) -> impl Iterator<Item = ((Vector3<f32>, u32), (Vector3<f32>, u32))> + '_ {
+        self.edges.iter().map(|&[a, b]| ((self.vertices[a], self.color(a)), (self.vertices[b], self.color(b))))
+    }
+
+    pub fn colored_triangles(&self) -> impl Iterator<Item = ([Vector3<f32>; 3], [Vector3<f32>; 3], [u32; 3])> + '_ {
+        self.faces.iter().map(|face| (face.map(|i| self.vertices[i]), face.map(|i| self.normals[i]), face.map(|i| self.color(i))))
+    }
+}
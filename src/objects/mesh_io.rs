@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use nalgebra::Vector3;
+use ply_rs::parser::Parser;
+use ply_rs::ply::{DefaultElement, Property};
+
+use crate::objects::{mesh::Mesh, pointclouds::{get_color, get_f32}};
+
+//attributs de sommet à écrire en plus des positions, s'ils existent dans le maillage et si le format les accepte
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ExportOptions {
+    pub normals: bool,
+    pub uvs: bool,
+    pub colors: bool,
+    pub ascii: bool, //PLY seulement, les PLY sont binaires par défaut
+}
+
+fn rgb(color: u32) -> [u8; 3] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
+
+//coordonnées de texture et couleurs à écrire
+type Attributes<'a> = (Option<&'a [(f32, f32)]>, Option<&'a [u32]>);
+
+//attributs demandés, une erreur si le maillage ne les a pas
+fn requested_attributes<'a>(mesh: &'a Mesh, options: &ExportOptions) -> Result<Attributes<'a>> {
+    let uvs = match options.uvs {
+        true => Some(mesh.uvs().ok_or_else(|| anyhow::anyhow!("Le maillage n'a pas de coordonnées de texture"))?),
+        false => None,
+    };
+    let colors = match options.colors {
+        true => Some(mesh.colors().ok_or_else(|| anyhow::anyhow!("Le maillage n'a pas de couleurs (colorer la surface avec --color-by)"))?),
+        false => None,
+    };
+
+    Ok((uvs, colors))
+}
+
+//les couleurs sont écrites après la position ("v x y z r g b", entre 0 et 1), une extension répandue du format
+pub fn save_obj(mesh: &Mesh, path: &Path, options: &ExportOptions) -> Result<()> {
+    let (uvs, colors) = requested_attributes(mesh, options)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "# manifolds-visualizer")?;
+
+    for (i, p) in mesh.vertices().iter().enumerate() {
+        match colors {
+            Some(colors) => {
+                let [r, g, b] = rgb(colors[i]).map(|c| c as f32 / 255.0);
+                writeln!(writer, "v {} {} {} {} {} {}", p.x, p.y, p.z, r, g, b)?;
+            }
+            None => writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?,
+        }
+    }
+    for (u, v) in uvs.into_iter().flatten() {
+        writeln!(writer, "vt {u} {v}")?;
+    }
+    if options.normals {
+        for n in mesh.normals() {
+            writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+    }
+
+    //les indices OBJ commencent à 1, un même indice sert pour la position, la texture et la normale
+    let corner = |i: usize| match (uvs.is_some(), options.normals) {
+        (true, true) => format!("{0}/{0}/{0}", i + 1),
+        (true, false) => format!("{0}/{0}", i + 1),
+        (false, true) => format!("{0}//{0}", i + 1),
+        (false, false) => format!("{}", i + 1),
+    };
+    for face in mesh.faces() {
+        writeln!(writer, "f {} {} {}", corner(face[0]), corner(face[1]), corner(face[2]))?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn save_ply(mesh: &Mesh, path: &Path, options: &ExportOptions) -> Result<()> {
+    let (uvs, colors) = requested_attributes(mesh, options)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    let format = if options.ascii { "ascii" } else { "binary_little_endian" };
+    writeln!(writer, "ply\nformat {format} 1.0\ncomment manifolds-visualizer")?;
+    writeln!(writer, "element vertex {}", mesh.vertices().len())?;
+    writeln!(writer, "property float x\nproperty float y\nproperty float z")?;
+    if options.normals {
+        writeln!(writer, "property float nx\nproperty float ny\nproperty float nz")?;
+    }
+    if uvs.is_some() {
+        writeln!(writer, "property float s\nproperty float t")?;
+    }
+    if colors.is_some() {
+        writeln!(writer, "property uchar red\nproperty uchar green\nproperty uchar blue")?;
+    }
+    writeln!(writer, "element face {}", mesh.faces().len())?;
+    writeln!(writer, "property list uchar int vertex_indices\nend_header")?;
+
+    for (i, p) in mesh.vertices().iter().enumerate() {
+        let mut floats = vec![p.x, p.y, p.z];
+        if options.normals {
+            floats.extend(mesh.normals()[i].iter());
+        }
+        if let Some(uvs) = uvs {
+            floats.extend([uvs[i].0, uvs[i].1]);
+        }
+        let bytes = colors.map(|colors| rgb(colors[i]));
+
+        if options.ascii {
+            let mut line = floats.iter().map(f32::to_string).collect::<Vec<_>>();
+            line.extend(bytes.into_iter().flatten().map(|b| b.to_string()));
+            writeln!(writer, "{}", line.join(" "))?;
+        } else {
+            for f in floats {
+                writer.write_all(&f.to_le_bytes())?;
+            }
+            if let Some(bytes) = bytes {
+                writer.write_all(&bytes)?;
+            }
+        }
+    }
+
+    for face in mesh.faces() {
+        if options.ascii {
+            writeln!(writer, "3 {} {} {}", face[0], face[1], face[2])?;
+        } else {
+            writer.write_all(&[3])?;
+            for &i in face {
+                writer.write_all(&(i as i32).to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+//STL binaire : une normale de face et trois sommets par triangle, sans normales aux sommets, textures ni couleurs
+pub fn save_stl(mesh: &Mesh, path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut header = [0u8; 80];
+    header[..20].copy_from_slice(b"manifolds-visualizer");
+    writer.write_all(&header)?;
+    writer.write_all(&(mesh.faces().len() as u32).to_le_bytes())?;
+
+    let write_vector = |writer: &mut BufWriter<File>, v: &Vector3<f32>| -> Result<()> {
+        for c in v.iter() {
+            writer.write_all(&c.to_le_bytes())?;
+        }
+        Ok(())
+    };
+
+    for &[a, b, c] in mesh.faces() {
+        let [pa, pb, pc] = [a, b, c].map(|i| mesh.vertices()[i]);
+        let normal = (pb - pa).cross(&(pc - pa)).try_normalize(f32::EPSILON).unwrap_or_else(Vector3::zeros);
+
+        for v in [&normal, &pa, &pb, &pc] {
+            write_vector(&mut writer, v)?;
+        }
+        writer.write_all(&[0, 0])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+//le format est déduit de l'extension (.obj, .ply ou .stl)
+pub fn save_mesh(mesh: &Mesh, path: &Path, options: &ExportOptions) -> Result<()> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("obj") => save_obj(mesh, path, options),
+        Some("ply") => save_ply(mesh, path, options),
+        Some("stl") => save_stl(mesh, path),
+        _ => Err(anyhow::anyhow!("Format de maillage non supporté pour '{}' (obj, ply ou stl attendu)", path.display())),
+    }
+}
+
+//les polygones sont découpés en éventail autour de leur premier sommet
+fn triangulate(polygon: &[usize]) -> impl Iterator<Item = [usize; 3]> + '_ {
+    (1..polygon.len().saturating_sub(1)).map(move |i| [polygon[0], polygon[i], polygon[i + 1]])
+}
+
+fn check_indices(faces: &[[usize; 3]], vertices_number: usize) -> Result<()> {
+    match faces.iter().flatten().find(|&&i| i >= vertices_number) {
+        Some(i) => Err(anyhow::anyhow!("Face avec un sommet {} inexistant ({} sommets)", i, vertices_number)),
+        None => Ok(()),
+    }
+}
+
+fn face_indices(property: &Property) -> Option<Vec<usize>> {
+    Some(match property {
+        Property::ListChar(list) => list.iter().map(|&i| i as usize).collect(),
+        Property::ListUChar(list) => list.iter().map(|&i| i as usize).collect(),
+        Property::ListShort(list) => list.iter().map(|&i| i as usize).collect(),
+        Property::ListUShort(list) => list.iter().map(|&i| i as usize).collect(),
+        Property::ListInt(list) => list.iter().map(|&i| i as usize).collect(),
+        Property::ListUInt(list) => list.iter().map(|&i| i as usize).collect(),
+        _ => return None,
+    })
+}
+
+//éléments 'vertex' et 'face' d'un PLY, les couleurs des sommets sont gardées si tous en ont une
+pub fn load_ply(path: &Path) -> Result<Mesh> {
+    let mut file = File::open(path)?;
+    let ply = Parser::<DefaultElement>::new().read_ply(&mut file)?;
+
+    let vertex_elements = ply.payload.get("vertex").ok_or_else(|| anyhow::anyhow!("Pas d'élément 'vertex' dans le PLY"))?;
+    let face_elements = ply
+        .payload
+        .get("face")
+        .ok_or_else(|| anyhow::anyhow!("Pas d'élément 'face' dans le PLY (pour un nuage de points, utiliser --cloud)"))?;
+
+    let vertices = vertex_elements
+        .iter()
+        .map(|v| Ok(Vector3::new(get_f32(v, "x")?, get_f32(v, "y")?, get_f32(v, "z")?)))
+        .collect::<Result<Vec<_>>>()?;
+    let colors: Option<Vec<u32>> = vertex_elements.iter().map(get_color).collect();
+
+    let mut faces = Vec::with_capacity(face_elements.len());
+    for face in face_elements {
+        let polygon = ["vertex_indices", "vertex_index"]
+            .iter()
+            .find_map(|&name| face.get(name))
+            .and_then(face_indices)
+            .ok_or_else(|| anyhow::anyhow!("Face sans liste d'indices 'vertex_indices'"))?;
+        faces.extend(triangulate(&polygon));
+    }
+    check_indices(&faces, vertices.len())?;
+
+    let mesh = Mesh::new(vertices, faces);
+    Ok(match colors {
+        Some(colors) => mesh.with_colors(colors),
+        None => mesh,
+    })
+}
+
+//positions ('v') et faces ('f') d'un OBJ, les normales sont recalculées et les autres directives ignorées
+//les indices commencent à 1, un indice négatif compte à partir du dernier sommet lu
+pub fn load_obj(path: &Path) -> Result<Mesh> {
+    let text = fs::read_to_string(path)?;
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let context = |e: anyhow::Error| anyhow::anyhow!("Ligne {} : {}", number + 1, e);
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                let coordinates = tokens
+                    .take(3)
+                    .map(|t| t.parse::<f32>().map_err(|_| anyhow::anyhow!("Coordonnée '{}' invalide", t)))
+                    .collect::<Result<Vec<_>>>()
+                    .map_err(context)?;
+                let [x, y, z] = coordinates[..] else {
+                    return Err(context(anyhow::anyhow!("Sommet à trois coordonnées attendu")));
+                };
+                vertices.push(Vector3::new(x, y, z));
+            }
+            Some("f") => {
+                let polygon = tokens
+                    .map(|corner| {
+                        let index = corner.split('/').next().unwrap_or_default();
+                        let index: i64 = index.parse().map_err(|_| anyhow::anyhow!("Indice '{}' invalide", index))?;
+                        match index {
+                            i if i > 0 => Ok(i as usize - 1),
+                            i if i < 0 && (-i) as usize <= vertices.len() => Ok(vertices.len() - (-i) as usize),
+                            _ => Err(anyhow::anyhow!("Indice {} hors limites", index)),
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()
+                    .map_err(context)?;
+                faces.extend(triangulate(&polygon));
+            }
+            _ => {}
+        }
+    }
+    check_indices(&faces, vertices.len())?;
+
+    Ok(Mesh::new(vertices, faces))
+}
+
+//STL texte ou binaire, les sommets identiques des triangles voisins sont fusionnés pour que chaque arête ne soit dessinée qu'une fois
+pub fn load_stl(path: &Path) -> Result<Mesh> {
+    let bytes = fs::read(path)?;
+
+    //un STL binaire peut lui aussi commencer par "solid", sa taille est un critère plus sûr
+    let binary_size = bytes.get(80..84).map(|count| 84 + 50 * u32::from_le_bytes(count.try_into().unwrap()) as usize);
+    let triangles = if binary_size == Some(bytes.len()) {
+        bytes[84..]
+            .chunks_exact(50)
+            .map(|record| {
+                let float = |i: usize| f32::from_le_bytes(record[4 * i..4 * i + 4].try_into().unwrap());
+                [1, 2, 3].map(|v| Vector3::new(float(3 * v), float(3 * v + 1), float(3 * v + 2)))
+            })
+            .collect::<Vec<_>>()
+    } else {
+        let text = std::str::from_utf8(&bytes).map_err(|_| anyhow::anyhow!("STL ni binaire ni texte"))?;
+        let points = text
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("vertex"))
+            .map(|coordinates| {
+                let coordinates = coordinates
+                    .split_whitespace()
+                    .map(|t| t.parse::<f32>().map_err(|_| anyhow::anyhow!("Coordonnée '{}' invalide", t)))
+                    .collect::<Result<Vec<_>>>()?;
+                match coordinates[..] {
+                    [x, y, z] => Ok(Vector3::new(x, y, z)),
+                    _ => Err(anyhow::anyhow!("Sommet à trois coordonnées attendu")),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if points.len() % 3 != 0 {
+            return Err(anyhow::anyhow!("Nombre de sommets ({}) qui n'est pas multiple de 3", points.len()));
+        }
+        points.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect()
+    };
+
+    let mut indices = HashMap::new();
+    let mut vertices = Vec::new();
+    let faces = triangles
+        .iter()
+        .map(|triangle| {
+            triangle.map(|p| {
+                *indices.entry(p.map(f32::to_bits)).or_insert_with(|| {
+                    vertices.push(p);
+                    vertices.len() - 1
+                })
+            })
+        })
+        .collect();
+
+    Ok(Mesh::new(vertices, faces))
+}
+
+//le format est déduit de l'extension (.obj, .ply ou .stl)
+pub fn load_mesh(path: &Path) -> Result<Mesh> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("obj") => load_obj(path),
+        Some("ply") => load_ply(path),
+        Some("stl") => load_stl(path),
+        _ => Err(anyhow::anyhow!("Format de maillage non supporté pour '{}' (obj, ply ou stl attendu)", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    //fichier temporaire propre au test, supprimé à la fin
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("manifolds-visualizer-{}-{}", std::process::id(), name)))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn tetrahedron() -> Mesh {
+        let vertices = vec![Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.5, 0.0, 0.0), Vector3::new(0.0, 2.25, 0.0), Vector3::new(0.1, 0.2, -3.0)];
+        let faces = vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]];
+        let uvs = vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (0.5, 0.5)];
+        let colors = vec![0xff0000, 0x00ff00, 0x0000ff, 0x808080];
+
+        Mesh::new(vertices, faces).with_uvs(uvs).with_colors(colors)
+    }
+
+    //mêmes nombres de sommets et de faces, et mêmes triangles (la fusion des sommets du STL peut les renuméroter)
+    fn assert_same_geometry(loaded: &Mesh, mesh: &Mesh) {
+        assert_eq!(loaded.vertices().len(), mesh.vertices().len());
+        assert_eq!(loaded.faces().len(), mesh.faces().len());
+        for ((a, _), (b, _)) in loaded.triangles().zip(mesh.triangles()) {
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn save_then_load() {
+        let mesh = tetrahedron();
+        let all = ExportOptions { normals: true, uvs: true, colors: true, ascii: false };
+
+        for (name, options) in [
+            ("plain.obj", ExportOptions::default()),
+            ("all.obj", all),
+            ("binary.ply", all),
+            ("ascii.ply", ExportOptions { ascii: true, ..all }),
+            ("mesh.stl", ExportOptions::default()),
+        ] {
+            let file = TempFile::new(name);
+            save_mesh(&mesh, &file.0, &options).unwrap();
+            let loaded = load_mesh(&file.0).unwrap();
+
+            assert_same_geometry(&loaded, &mesh);
+            if name.ends_with(".ply") {
+                assert_eq!(loaded.colors(), mesh.colors());
+            }
+        }
+    }
+
+    //un indice négatif compte à partir du dernier sommet lu, pas du dernier sommet du fichier
+    #[test]
+    fn obj_negative_indices() {
+        let file = TempFile::new("negative.obj");
+        let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 0 0 1\nf 1/1 -1/2 -2/3\n";
+
+        fs::write(&file.0, text).unwrap();
+        assert_eq!(load_obj(&file.0).unwrap().faces(), [[0, 1, 2], [0, 3, 2]]);
+
+        fs::write(&file.0, format!("{text}f 1 2 -5\n")).unwrap();
+        let error = load_obj(&file.0).err().expect("l'indice -5 est hors limites").to_string();
+        assert!(error.starts_with("Ligne 7"), "{error}");
+    }
+
+    //un STL binaire dont l'en-tête commence par "solid" reste lu comme binaire, sa taille correspondant au nombre de triangles
+    #[test]
+    fn stl_binary_or_ascii() {
+        let mesh = tetrahedron();
+        let binary = TempFile::new("solid.stl");
+        save_stl(&mesh, &binary.0).unwrap();
+        let mut bytes = fs::read(&binary.0).unwrap();
+        bytes[..12].copy_from_slice(b"solid binary");
+        fs::write(&binary.0, &bytes).unwrap();
+        assert_same_geometry(&load_stl(&binary.0).unwrap(), &mesh);
+
+        let ascii = TempFile::new("ascii.stl");
+        let facets: String = mesh
+            .triangles()
+            .map(|(triangle, _)| {
+                let vertices: String = triangle.iter().map(|p| format!("      vertex {} {} {}\n", p.x, p.y, p.z)).collect();
+                format!("  facet normal 0 0 0\n    outer loop\n{vertices}    endloop\n  endfacet\n")
+            })
+            .collect();
+        fs::write(&ascii.0, format!("solid ascii\n{facets}endsolid ascii\n")).unwrap();
+        assert_same_geometry(&load_stl(&ascii.0).unwrap(), &mesh);
+
+        fs::write(&ascii.0, "solid broken\n  facet normal 0 0 1\n    outer loop\n      vertex 0 0 0\n      vertex 1 0 0\n").unwrap();
+        assert!(load_stl(&ascii.0).is_err());
+    }
+}
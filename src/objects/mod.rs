@@ -1,12 +1,23 @@
+use std::path::Path;
+
 use derive_more::Constructor;
-use nalgebra::{Matrix4, Vector3};
+use nalgebra::{Matrix3, Matrix4, Vector3};
 
-use crate::objects::{pointclouds::PointCloud, surfaces::Surface};
+use crate::{drawing::colormap::Legend, objects::{bounds::{BoundingBox, BoundingSphere}, coloring::SurfaceColoring, curves::{Curve, CurveSpec}, geodesics::GeodesicSpec, implicit::ImplicitSpec, mesh::Mesh, mesh_io::load_mesh, pointclouds::PointCloud, surfaces::{Surface, SurfaceSpec}}};
 
 pub mod surfaces;
 pub mod pointclouds;
+pub mod bounds;
+pub mod mesh;
+pub mod mesh_io;
+pub mod implicit;
+pub mod curves;
+pub mod differential;
+pub mod coloring;
+pub mod geodesics;
 
 const DEFAULT_LINE_COLOR: u32 = 0xffffff;
+const DEFAULT_FACE_COLOR: u32 = 0x000000; //pas de couleur, c'est celle de l'objet qui sera utilisée
 
 #[derive(Constructor)]
 pub struct Object {
@@ -16,6 +27,58 @@ pub struct Object {
 }
 
 impl Object {
+    pub fn color(&self) -> u32 {
+        self.color
+    }
+
+    //échoue si l'objet n'est pas une surface, seules les surfaces ont une grille (u, v) où évaluer un champ
+    pub fn set_coloring(&mut self, coloring: &SurfaceColoring) -> anyhow::Result<()> {
+        match &mut self.geometry {
+            Geometry::Surface(surface) => {
+                surface.set_coloring(coloring);
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("Seules les surfaces paramétrées peuvent être colorées par un champ")),
+        }
+    }
+
+    pub fn legend(&self) -> Option<&Legend> {
+        match &self.geometry {
+            Geometry::Surface(surface) => surface.legend(),
+            _ => None,
+        }
+    }
+
+    //accès direct aux colonnes d'un nuage de points, pour le rendu sans passer par les primitives
+    pub fn point_cloud(&self) -> Option<&PointCloud> {
+        match &self.geometry {
+            Geometry::PointCloud(cloud) => Some(cloud),
+            _ => None,
+        }
+    }
+
+    //sphère englobante dans le repère de l'objet, avant sa transformation
+    pub fn geometry_bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.geometry.bounding_sphere()
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.geometry.bounding_box().transformed(&self.local_to_world)
+    }
+
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.geometry.bounding_sphere().map(|sphere| sphere.transformed(&self.local_to_world))
+    }
+
+    //volumes englobants dans le monde pour un objet enfant : local_to_world est alors relatif au repère du parent
+    pub fn bounding_box_under(&self, parent_to_world: Matrix4<f32>) -> BoundingBox {
+        self.geometry.bounding_box().transformed(&(parent_to_world * self.local_to_world))
+    }
+
+    pub fn bounding_sphere_under(&self, parent_to_world: Matrix4<f32>) -> Option<BoundingSphere> {
+        self.geometry.bounding_sphere().map(|sphere| sphere.transformed(&(parent_to_world * self.local_to_world)))
+    }
+
     pub fn raw_primitives(&self) -> impl Iterator<Item = Primitive3f> {
         self.geometry.primitives().map(|elm| elm.with_color(self.color))
     }
@@ -24,6 +87,15 @@ impl Object {
         self.raw_primitives().map(|p| p.transformed(self.local_to_world))
     }
 
+    pub fn raw_triangles(&self) -> impl Iterator<Item = Primitive3f> {
+        self.geometry.triangles().map(|elm| elm.with_color(self.color))
+    }
+
+    //primitives pleines (triangles) pour le rendu ombré, dans le repère du monde
+    pub fn triangles(&self) -> impl Iterator<Item = Primitive3f> {
+        self.raw_triangles().map(|p| p.transformed(self.local_to_world))
+    }
+
     pub fn rotate_x(&mut self, d_angle: f32) {
         let cos = d_angle.cos();
         let sin = d_angle.sin();
@@ -35,7 +107,7 @@ impl Object {
             0.0, 0.0, 0.0, 1.0,
         );
 
-        self.local_to_world = self.local_to_world * rotation_matrix;
+        self.local_to_world *= rotation_matrix;
     }
 
     pub fn rotate_y(&mut self, d_angle: f32) {
@@ -49,7 +121,7 @@ impl Object {
             0.0, 0.0, 0.0,  1.0
         );
 
-        self.local_to_world = self.local_to_world * rotation_matrix;
+        self.local_to_world *= rotation_matrix;
     }
 
     pub fn rotate_z(&mut self, d_angle: f32) {
@@ -63,7 +135,7 @@ impl Object {
             0.0, 0.0,  0.0, 1.0,
          );
 
-         self.local_to_world = self.local_to_world * rotation_matrix;
+         self.local_to_world *= rotation_matrix;
     }
 
     pub fn translate_relative(&mut self, dp: Vector3<f32>) {
@@ -74,7 +146,7 @@ impl Object {
             0.0, 0.0, 0.0, 1.0
         );
 
-        self.local_to_world = self.local_to_world * translation_matrix;
+        self.local_to_world *= translation_matrix;
     }
 
     pub fn translate_absolute(&mut self, dp: Vector3<f32>) {
@@ -92,17 +164,97 @@ impl Object {
 pub enum Geometry {
     Surface(Surface),
     PointCloud(PointCloud),
+    Mesh(Mesh),
+    Curve(Curve),
+    Paths(Vec<Vec<Vector3<f32>>>), //polylignes, comme les géodésiques tracées sur une surface
+}
+
+//d'où vient la géométrie d'un objet, pour pouvoir la recharger (fichiers de scène)
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectSource {
+    Surface(SurfaceSpec),
+    Cloud(String),
+    Mesh(String), //fichier .obj, .ply ou .stl
+    Implicit(ImplicitSpec),
+    Curve(CurveSpec),
+    Geodesic(GeodesicSpec),
+}
+
+impl ObjectSource {
+    pub fn load(&self) -> anyhow::Result<Geometry> {
+        match self {
+            ObjectSource::Surface(spec) => Ok(Geometry::Surface(spec.build())),
+            ObjectSource::Cloud(path) => PointCloud::new_from_path(path)
+                .map(Geometry::PointCloud)
+                .map_err(|e| anyhow::anyhow!("Echec du chargement de '{}' : {}", path, e)),
+            ObjectSource::Mesh(path) => load_mesh(Path::new(path))
+                .map(Geometry::Mesh)
+                .map_err(|e| anyhow::anyhow!("Echec du chargement de '{}' : {}", path, e)),
+            ObjectSource::Implicit(spec) => Ok(Geometry::Mesh(spec.build().polygonize())),
+            ObjectSource::Curve(spec) => Ok(Geometry::Curve(spec.build())),
+            ObjectSource::Geodesic(spec) => spec.build().map(Geometry::Paths),
+        }
+    }
 }
 
 impl Geometry {
+    //sommets de la géométrie dans le repère de l'objet (grille d'échantillonnage pour les surfaces et les courbes)
+    pub fn points(&self) -> Box<dyn Iterator<Item = Vector3<f32>> + '_> {
+        match self {
+            Geometry::Surface(surface) => Box::new(surface.points()),
+            Geometry::PointCloud(cloud) => Box::new(cloud.positions().iter().copied()),
+            Geometry::Mesh(mesh) => Box::new(mesh.vertices().iter().copied()),
+            Geometry::Curve(curve) => Box::new(curve.points()),
+            Geometry::Paths(paths) => Box::new(paths.iter().flatten().copied()),
+        }
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(self.points())
+    }
+
+    //None pour une géométrie sans point
+    //un nuage est parcouru directement, sans recopier ses positions
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        if let Geometry::PointCloud(cloud) = self {
+            return BoundingSphere::from_points(cloud.positions().iter().copied());
+        }
+        let points: Vec<Vector3<f32>> = self.points().collect();
+        BoundingSphere::from_points(points.iter().copied())
+    }
+
     pub fn primitives(&self) -> impl Iterator<Item = Primitive3f> + '_ {
         match self {
-            Geometry::Surface(surface) => either::Left(surface.isolines().map(Primitive3f::from)),
-            Geometry::PointCloud(cloud) => either::Right(cloud.points().map(Primitive3f::from)),
+            Geometry::Surface(surface) => Box::new(surface.colored_isolines().map(Primitive3f::from)) as Box<dyn Iterator<Item = Primitive3f>>,
+            Geometry::PointCloud(cloud) => Box::new(cloud.points().map(Primitive3f::from)),
+            Geometry::Mesh(mesh) if mesh.colors().is_some() => Box::new(mesh.colored_edges().map(Primitive3f::from)),
+            Geometry::Mesh(mesh) => Box::new(mesh.edges().map(Primitive3f::from)),
+            Geometry::Curve(curve) => Box::new(curve.segments().map(curve_segment)),
+            Geometry::Paths(paths) => Box::new(path_segments(paths).map(curve_segment)),
+        }
+    }
+
+    //un nuage de points, une courbe ou des chemins n'ont pas de faces, ils restent rendus en points et en segments
+    pub fn triangles(&self) -> impl Iterator<Item = Primitive3f> + '_ {
+        match self {
+            Geometry::Surface(surface) => Box::new(surface.colored_triangles().map(Primitive3f::from)) as Box<dyn Iterator<Item = Primitive3f>>,
+            Geometry::PointCloud(cloud) => Box::new(cloud.points().map(Primitive3f::from)),
+            Geometry::Mesh(mesh) => Box::new(mesh.colored_triangles().map(Primitive3f::from)),
+            Geometry::Curve(curve) => Box::new(curve.segments().map(curve_segment)),
+            Geometry::Paths(paths) => Box::new(path_segments(paths).map(curve_segment)),
         }
     }
 }
 
+fn path_segments(paths: &[Vec<Vector3<f32>>]) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> + '_ {
+    paths.iter().flat_map(|path| path.windows(2).map(|w| (w[0], w[1])))
+}
+
+//contrairement aux isolignes, les segments d'une courbe prennent la couleur de l'objet
+fn curve_segment((p0, p1): (Vector3<f32>, Vector3<f32>)) -> Primitive3f {
+    Primitive3f::Line(Line3f::new(Point3f::new(p0, DEFAULT_FACE_COLOR), Point3f::new(p1, DEFAULT_FACE_COLOR)))
+}
+
 impl From<(Vector3<f32>, Vector3<f32>)> for Primitive3f {
     fn from(value: (Vector3<f32>, Vector3<f32>)) -> Self {
         Self::Line(Line3f::new(Point3f::new(value.0, DEFAULT_LINE_COLOR), Point3f::new(value.1, DEFAULT_LINE_COLOR))) //blanc par defaut
@@ -115,15 +267,38 @@ impl From<Vector3<f32>> for Primitive3f {
     }
 }
 
+impl From<([Vector3<f32>; 3], [Vector3<f32>; 3])> for Primitive3f {
+    fn from((positions, normals): ([Vector3<f32>; 3], [Vector3<f32>; 3])) -> Self {
+        let [p0, p1, p2] = positions.map(|p| Point3f::new(p, DEFAULT_FACE_COLOR));
+        Self::Triangle(Triangle3f::new(p0, p1, p2, normals))
+    }
+}
+
+//sommets colorés (surfaces colorées par un champ), une couleur nulle laisse celle de l'objet
+impl From<((Vector3<f32>, u32), (Vector3<f32>, u32))> for Primitive3f {
+    fn from(((p0, c0), (p1, c1)): ((Vector3<f32>, u32), (Vector3<f32>, u32))) -> Self {
+        Self::Line(Line3f::new(Point3f::new(p0, c0), Point3f::new(p1, c1)))
+    }
+}
+
+impl From<([Vector3<f32>; 3], [Vector3<f32>; 3], [u32; 3])> for Primitive3f {
+    fn from((positions, normals, colors): ([Vector3<f32>; 3], [Vector3<f32>; 3], [u32; 3])) -> Self {
+        let [p0, p1, p2] = [0, 1, 2].map(|i| Point3f::new(positions[i], colors[i]));
+        Self::Triangle(Triangle3f::new(p0, p1, p2, normals))
+    }
+}
+
 impl From<Point3f> for Primitive3f {
     fn from(value: Point3f) -> Self {
         Self::Point(value)
     }
 }
 
+#[derive(Clone)]
 pub enum Primitive3f {
     Line(Line3f),
     Point(Point3f),
+    Triangle(Triangle3f),
 }
 
 impl Primitive3f {
@@ -148,10 +323,19 @@ impl Primitive3f {
                     Self::Point(Point3f::new(point.p, color))
                 }
             },
+            Self::Triangle(triangle) => {
+                if triangle.p0.color > 0 {
+                    self
+                }
+                else {
+                    let [p0, p1, p2] = [&triangle.p0, &triangle.p1, &triangle.p2].map(|p| Point3f::new(p.p, color));
+                    Self::Triangle(Triangle3f::new(p0, p1, p2, triangle.normals))
+                }
+            }
         }
     }
 
-    fn transformed(self, transform: Matrix4<f32>) -> Self {
+    pub fn transformed(self, transform: Matrix4<f32>) -> Self {
         match self {
             Primitive3f::Point(point) => Primitive3f::Point(point.transformed(transform)),
 
@@ -161,35 +345,67 @@ impl Primitive3f {
 
                 Primitive3f::Line(Line3f::new(p0, p1))
             }
+
+            Primitive3f::Triangle(triangle) => {
+                let normal_matrix = normal_matrix(&transform);
+                let normals = triangle.normals.map(|n| (normal_matrix * n).try_normalize(f32::EPSILON).unwrap_or_else(Vector3::zeros));
+
+                Primitive3f::Triangle(Triangle3f::new(
+                    triangle.p0.transformed(transform),
+                    triangle.p1.transformed(transform),
+                    triangle.p2.transformed(transform),
+                    normals,
+                ))
+            }
         }
     }
 }
 
-#[derive(Constructor)]
+#[derive(Constructor, Clone)]
 pub struct Line3f {
     pub p0: Point3f,
     pub p1: Point3f,
 }
 
 impl Line3f {
+    //chaque extrémité garde sa couleur
     pub fn inverted(&self) -> Self {
-        let p1 = self.p0.p;
-        let p0 = self.p1.p;
-        let color = self.p0.color;
-
         Self {
-            p1: Point3f::new(p1, color),
-            p0: Point3f::new(p0, color),
+            p0: self.p1.clone(),
+            p1: self.p0.clone(),
         }
     }
 }
 
+//triangle plein, avec une normale par sommet pour l'ombrage de Gouraud
+#[derive(Constructor, Clone)]
+pub struct Triangle3f {
+    pub p0: Point3f,
+    pub p1: Point3f,
+    pub p2: Point3f,
+    pub normals: [Vector3<f32>; 3],
+}
+
+impl Triangle3f {
+    pub fn face_normal(&self) -> Vector3<f32> {
+        (self.p1.p - self.p0.p).cross(&(self.p2.p - self.p0.p))
+    }
+}
+
 #[derive(Constructor, Clone)]
 pub struct Point3f {
     pub p: Vector3<f32>,
     pub color: u32,
 }
 
+//matrice appliquée aux normales : la transposée de l'inverse de la partie linéaire, pour rester juste avec une échelle non uniforme ou un cisaillement
+//on prend les cofacteurs (déterminant fois la transposée de l'inverse) au signe du déterminant près, les normales étant renormalisées ensuite
+fn normal_matrix(transform: &Matrix4<f32>) -> Matrix3<f32> {
+    let linear = transform.fixed_view::<3, 3>(0, 0);
+    let (a, b, c) = (linear.column(0), linear.column(1), linear.column(2));
+    Matrix3::from_columns(&[b.cross(&c), c.cross(&a), a.cross(&b)]) * linear.determinant().signum()
+}
+
 impl Point3f {
     pub fn transformed(self, transform: Matrix4<f32>) -> Self {
         let mut homogeneous = self.p.to_homogeneous();
@@ -198,4 +414,19 @@ impl Point3f {
         let homogeneous = transform * homogeneous;
         Self::new(Vector3::<f32>::new(homogeneous.x, homogeneous.y, homogeneous.z), self.color)
     }
-}
\ No newline at end of file
+
+    //interpolation linéaire de la position et de la couleur entre deux points
+    pub fn lerp(&self, other: &Point3f, t: f32) -> Self {
+        Self::new(self.p + (other.p - self.p) * t, lerp_color(self.color, other.color, t))
+    }
+}
+
+pub fn lerp_color(c0: u32, c1: u32, t: f32) -> u32 {
+    let channel = |shift: u32| {
+        let a = ((c0 >> shift) & 0xff) as f32;
+        let b = ((c1 >> shift) & 0xff) as f32;
+        ((a + (b - a) * t).round().clamp(0.0, 255.0) as u32) << shift
+    };
+
+    channel(16) | channel(8) | channel(0)
+}
@@ -1,58 +1,189 @@
-use std::fs::File;
-use std::path::Path;
+use std::{fs::File, io::BufReader, path::Path};
 
 use anyhow::Result;
-use derive_more::Constructor;
-use nalgebra::Vector3;
-use ply_rs::parser::Parser;
-use ply_rs::ply::{DefaultElement, Property};
 use linked_hash_map::LinkedHashMap;
+use nalgebra::Vector3;
+use ply_rs::{parser::Parser, ply::{DefaultElement, Property, PropertyAccess, PropertyType}};
 
 use crate::objects::Point3f;
 
 pub const DEFAULT_POINT_COLOR: u32 = 0x000000;
 
-#[derive(Constructor)]
+//nuage stocké par colonnes (positions, couleurs, attributs) plutôt qu'en Vec<Point3f>, pour pouvoir en parcourir une seule sans copie
 pub struct PointCloud {
-    points: Vec<Point3f>,
+    positions: Vec<Vector3<f32>>,
+    colors: Option<Vec<u32>>, //sans couleurs, les points prennent celle de l'objet
+    attributes: Vec<(String, Vec<f32>)>, //propriétés scalaires supplémentaires du PLY (intensité, normales...), une valeur par point
 }
 
 impl PointCloud {
+    pub fn new(positions: Vec<Vector3<f32>>) -> Self {
+        Self { positions, colors: None, attributes: Vec::new() }
+    }
+
+    pub fn with_colors(mut self, colors: Vec<u32>) -> Self {
+        assert_eq!(colors.len(), self.positions.len(), "Une couleur par point");
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn with_attribute(mut self, name: impl Into<String>, values: Vec<f32>) -> Self {
+        assert_eq!(values.len(), self.positions.len(), "Une valeur d'attribut par point");
+        self.attributes.push((name.into(), values));
+        self
+    }
+
     pub fn new_from_path(path: &str) -> anyhow::Result<Self> {
-        load_point_cloud(path).map(Self::new)
+        load_point_cloud(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn positions(&self) -> &[Vector3<f32>] {
+        &self.positions
+    }
+
+    pub fn colors(&self) -> Option<&[u32]> {
+        self.colors.as_deref()
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&[f32]> {
+        self.attributes.iter().find(|(n, _)| n == name).map(|(_, values)| values.as_slice())
+    }
+
+    pub fn attribute_names(&self) -> impl Iterator<Item = &str> {
+        self.attributes.iter().map(|(name, _)| name.as_str())
+    }
+
+    //couleur du point, DEFAULT_POINT_COLOR (celle de l'objet) si le nuage n'est pas coloré
+    pub fn color(&self, i: usize) -> u32 {
+        self.colors.as_ref().map_or(DEFAULT_POINT_COLOR, |colors| colors[i])
     }
 
-    pub fn points(&self) -> impl Iterator<Item = Point3f> {
-        self.points.clone().into_iter() //il faut faire ça proprement ici, cloner le nuage n'est pas acceptable, mais bon pour faire simple au debut ça va
+    pub fn points(&self) -> impl Iterator<Item = Point3f> + '_ {
+        self.positions.iter().enumerate().map(|(i, &p)| Point3f::new(p, self.color(i)))
     }
 }
 
-pub fn load_point_cloud(path: &str) -> Result<Vec<Point3f>> {
-    let mut file = File::open(Path::new(path))?;
-    let parser = Parser::<DefaultElement>::new();
-    let ply = parser.read_ply(&mut file)?;
+//rôle d'une propriété scalaire de l'élément "vertex"
+enum VertexProperty {
+    Coordinate(usize),
+    Channel(usize),
+    Attribute,
+}
 
-    let vertices = ply
-        .payload
-        .get("vertex")
-        .ok_or_else(|| anyhow::anyhow!("Pas d'élément 'vertex' dans le PLY"))?;
+fn vertex_property(name: &str) -> VertexProperty {
+    match name {
+        "x" => VertexProperty::Coordinate(0),
+        "y" => VertexProperty::Coordinate(1),
+        "z" => VertexProperty::Coordinate(2),
+        "red" | "r" | "diffuse_red" => VertexProperty::Channel(0),
+        "green" | "g" | "diffuse_green" => VertexProperty::Channel(1),
+        "blue" | "b" | "diffuse_blue" => VertexProperty::Channel(2),
+        _ => VertexProperty::Attribute,
+    }
+}
 
-    let mut points = Vec::with_capacity(vertices.len());
+//sommet lu directement, sans la table de propriétés allouée pour chaque sommet par DefaultElement
+#[derive(Default)]
+struct CloudVertex {
+    position: Vector3<f32>,
+    rgb: [u8; 3],
+    attributes: Vec<f32>, //dans l'ordre des propriétés de l'en-tête
+}
 
-    for v in vertices.iter() {
-        let x = get_f32(v, "x")?;
-        let y = get_f32(v, "y")?;
-        let z = get_f32(v, "z")?;
+impl PropertyAccess for CloudVertex {
+    fn new() -> Self {
+        Self::default()
+    }
 
-        let color = get_color(v).unwrap_or(DEFAULT_POINT_COLOR);
+    fn set_property(&mut self, name: String, property: Property) {
+        match vertex_property(&name) {
+            VertexProperty::Coordinate(i) => self.position[i] = property_to_f32(&property).unwrap_or(0.0),
+            VertexProperty::Channel(i) => self.rgb[i] = property_to_u8(&property).unwrap_or(0),
+            VertexProperty::Attribute => {
+                if let Some(value) = property_to_f32(&property) {
+                    self.attributes.push(value);
+                }
+            }
+        }
+    }
+}
 
-        points.push(Point3f::new(Vector3::new(x, y, z), color));
+//seul l'élément "vertex" est conservé, les éléments qui le précèdent dans le fichier sont lus puis ignorés
+pub fn load_point_cloud(path: &str) -> Result<PointCloud> {
+    let mut reader = BufReader::new(File::open(Path::new(path))?);
+    let header = Parser::<DefaultElement>::new().read_header(&mut reader)?;
+
+    for element in header.elements.values() {
+        if element.name != "vertex" {
+            Parser::<DefaultElement>::new().read_payload_for_element(&mut reader, element, &header)?;
+            continue;
+        }
+
+        let scalars: Vec<&str> = element
+            .properties
+            .values()
+            .filter(|property| matches!(property.data_type, PropertyType::Scalar(_)))
+            .map(|property| property.name.as_str())
+            .collect();
+
+        for coordinate in ["x", "y", "z"] {
+            if !scalars.contains(&coordinate) {
+                return Err(anyhow::anyhow!("Vertex sans propriété '{}'", coordinate));
+            }
+        }
+        let colored = (0..3).all(|c| scalars.iter().any(|&name| matches!(vertex_property(name), VertexProperty::Channel(i) if i == c)));
+        let attribute_names: Vec<&str> = scalars.iter().copied().filter(|&name| matches!(vertex_property(name), VertexProperty::Attribute)).collect();
+
+        let vertices = Parser::<CloudVertex>::new().read_payload_for_element(&mut reader, element, &header)?;
+
+        let mut positions = Vec::with_capacity(vertices.len());
+        let mut colors = Vec::with_capacity(if colored { vertices.len() } else { 0 });
+        let mut attributes = vec![Vec::with_capacity(vertices.len()); attribute_names.len()];
+
+        for vertex in vertices {
+            positions.push(vertex.position);
+            if colored {
+                let [r, g, b] = vertex.rgb;
+                colors.push(((r as u32) << 16) | ((g as u32) << 8) | (b as u32));
+            }
+            for (column, value) in attributes.iter_mut().zip(vertex.attributes) {
+                column.push(value);
+            }
+        }
+
+        let mut cloud = PointCloud::new(positions);
+        if colored {
+            cloud = cloud.with_colors(colors);
+        }
+        return Ok(attribute_names.into_iter().zip(attributes).fold(cloud, |cloud, (name, values)| cloud.with_attribute(name, values)));
     }
 
-    Ok(points)
+    Err(anyhow::anyhow!("Pas d'élément 'vertex' dans le PLY"))
+}
+
+fn property_to_f32(p: &Property) -> Option<f32> {
+    Some(match p {
+        Property::Char(v) => *v as f32,
+        Property::UChar(v) => *v as f32,
+        Property::Short(v) => *v as f32,
+        Property::UShort(v) => *v as f32,
+        Property::Int(v) => *v as f32,
+        Property::UInt(v) => *v as f32,
+        Property::Float(v) => *v,
+        Property::Double(v) => *v as f32,
+        _ => return None,
+    })
 }
 
-fn get_f32(v: &LinkedHashMap<String, Property>, name: &str) -> Result<f32> {
+pub(crate) fn get_f32(v: &LinkedHashMap<String, Property>, name: &str) -> Result<f32> {
     let p = v
         .get(name)
         .ok_or_else(|| anyhow::anyhow!("Vertex sans propriété '{}'", name))?;
@@ -82,7 +213,7 @@ fn property_to_u8(p: &Property) -> Option<u8> {
     })
 }
 
-fn get_color(v: &LinkedHashMap<String, Property>) -> Option<u32> {
+pub(crate) fn get_color(v: &LinkedHashMap<String, Property>) -> Option<u32> {
     let try_get = |names: &[&str]| {
         names.iter()
             .find_map(|&n| v.get(n))
@@ -1,31 +1,38 @@
-use std::f32::consts::{FRAC_PI_2, PI};
-use derive_more::Constructor;
+use std::{collections::HashMap, f32::consts::{FRAC_PI_2, PI}, fmt, str::FromStr};
 use itertools::Itertools;
 use nalgebra::Vector3;
 
+use crate::{drawing::colormap::Legend, expression::Expression, objects::{coloring::SurfaceColoring, curves::{Curve, CurveSpec, FrameKind}, differential::{Partials, PrincipalCurvatures, QuadraticForm}, mesh::Mesh}};
+
 pub struct Surface {
     parts: Vec<SurfaceParam>,
     u_points_number: u32,
     v_points_number: u32,
+    colors: Option<Vec<Vec<u32>>>, //une couleur par sommet de la grille de chaque morceau, si la surface est colorée par un champ
+    legend: Option<Legend>,
 }
 
 impl Surface {
+    fn from_parts(parts: Vec<SurfaceParam>, u_points_number: u32, v_points_number: u32) -> Self {
+        Self { parts, u_points_number, v_points_number, colors: None, legend: None }
+    }
+
     pub fn new_sphere(r: f32, u_points_number: u32, v_points_number: u32) -> Self {
         let parts = vec![SurfaceParam::new_sphere(r)];
 
-        Self {parts, u_points_number, v_points_number}
+        Self::from_parts(parts, u_points_number, v_points_number)
     }
 
     pub fn new_torus(r: f32, r_rev: f32, u_points_number: u32, v_points_number: u32) -> Self {
         let parts = vec![SurfaceParam::new_torus(r, r_rev)];
 
-        Self {parts, u_points_number, v_points_number}
+        Self::from_parts(parts, u_points_number, v_points_number)
     }
 
     pub fn new_mobius(r: f32, w: f32, u_points_number: u32, v_points_number: u32) -> Self {
         let parts = vec![SurfaceParam::new_mobius(r, w)];
 
-        Self {parts, u_points_number, v_points_number}
+        Self::from_parts(parts, u_points_number, v_points_number)
     }
 
     pub fn new_cube_sphere(r: f32, n: u32) -> Self {
@@ -33,11 +40,11 @@ impl Surface {
             .map(|i| SurfaceParam::cube_sphere_face(r, i))
             .collect();
 
-        Self {
-            parts,
-            u_points_number: n,
-            v_points_number: n,
-        }
+        Self::from_parts(parts, n, n)
+    }
+
+    pub fn new_from_param(param: SurfaceParam, u_points_number: u32, v_points_number: u32) -> Self {
+        Self::from_parts(vec![param], u_points_number, v_points_number)
     }
 
     pub fn new_boy(scale: f32, u: u32, v: u32) -> Self {
@@ -45,11 +52,34 @@ impl Surface {
             SurfaceParam::new_boy(scale),
         ];
 
-        Self {
-            parts,
-            u_points_number: u,
-            v_points_number: v,
-        }
+        Self::from_parts(parts, u, v)
+    }
+
+    //tube de rayon r autour de la courbe, échantillonné comme elle le long de t
+    pub fn new_tube(curve: Curve, r: f32, v_points_number: u32, frame: FrameKind) -> Self {
+        let u_points_number = curve.points_number();
+        let parts = vec![SurfaceParam::new_tube(curve, r, frame)];
+
+        Self::from_parts(parts, u_points_number, v_points_number)
+    }
+
+    pub fn new_ribbon(curve: Curve, w: f32, v_points_number: u32, frame: FrameKind) -> Self {
+        let u_points_number = curve.points_number();
+        let parts = vec![SurfaceParam::new_ribbon(curve, w, frame)];
+
+        Self::from_parts(parts, u_points_number, v_points_number)
+    }
+
+    pub fn parts(&self) -> &[SurfaceParam] {
+        &self.parts
+    }
+
+    //points de la grille d'échantillonnage de tous les morceaux
+    pub fn points(&self) -> impl Iterator<Item = Vector3<f32>> + '_ {
+        self.parts.iter().flat_map(move |part| {
+            let (u_values, v_values) = part.grid(self.u_points_number, self.v_points_number);
+            u_values.into_iter().flat_map(move |u| v_values.clone().into_iter().map(move |v| part.point(u, v)))
+        })
     }
 
     pub fn isos_u(&self) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> {
@@ -57,7 +87,7 @@ impl Surface {
     }
 
     pub fn isos_v(&self) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> {
-        self.parts.iter().flat_map(move |p| p.isos_v(self.u_points_number, self.v_points_number))
+        self.parts.iter().flat_map(move |p| p.isos_v(self.v_points_number, self.u_points_number))
     }
 
     pub fn isolines(
@@ -65,16 +95,446 @@ impl Surface {
     ) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> {
         self.parts.iter().flat_map(move |p| p.isos_u(self.u_points_number, self.v_points_number).chain(p.isos_v(self.v_points_number, self.u_points_number)))
     }
+
+    pub fn triangles(&self) -> impl Iterator<Item = ([Vector3<f32>; 3], [Vector3<f32>; 3])> + '_ {
+        self.parts.iter().flat_map(move |p| p.triangles(self.u_points_number, self.v_points_number))
+    }
+
+    //colore les sommets de la grille par un champ scalaire, l'intervalle automatique est commun à tous les morceaux
+    pub fn set_coloring(&mut self, coloring: &SurfaceColoring) {
+        let values: Vec<Vec<f32>> = self.parts
+            .iter()
+            .map(|part| {
+                let (u_values, v_values) = part.grid(self.u_points_number, self.v_points_number);
+                u_values
+                    .iter()
+                    .flat_map(|&u| v_values.iter().map(move |&v| coloring.field.evaluate(part, u, v).unwrap_or(f32::NAN)))
+                    .collect()
+            })
+            .collect();
+
+        let range = coloring.resolve_range(&values.concat());
+        let legend = Legend::new(coloring.field.to_string(), coloring.colormap, range);
+
+        self.colors = Some(values.iter().map(|part| part.iter().map(|&value| legend.color(value)).collect()).collect());
+        self.legend = Some(legend);
+    }
+
+    pub fn legend(&self) -> Option<&Legend> {
+        self.legend.as_ref()
+    }
+
+    //maillage indexé sur la grille d'échantillonnage, un sommet par point de la grille de chaque morceau, avec les mêmes faces que triangles()
+    //les coordonnées de texture sont les paramètres (u, v), les couleurs celles du champ si la surface est colorée
+    //avec weld, les sommets confondus (coutures des surfaces fermées, pôles, bords communs des morceaux) sont fusionnés
+    //et les faces devenues dégénérées supprimées, le sommet fusionné garde les (u, v) et la couleur du premier
+    pub fn to_mesh(&self, weld: bool) -> Mesh {
+        let (mut vertices, mut normals, mut uvs, mut faces) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+
+        for part in &self.parts {
+            let (u_values, v_values) = part.grid(self.u_points_number, self.v_points_number);
+            let (offset, nv) = (vertices.len(), v_values.len());
+
+            for &u in &u_values {
+                for &v in &v_values {
+                    vertices.push(part.point(u, v));
+                    normals.push(part.normal(u, v));
+                    uvs.push((u, v));
+                }
+            }
+
+            for (i, j) in (0..u_values.len().saturating_sub(1)).cartesian_product(0..nv.saturating_sub(1)) {
+                let corner = |di: usize, dj: usize| offset + (i + di) * nv + j + dj;
+                let (a, b, c, d) = (corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1));
+                faces.extend([[a, b, c], [a, c, d]]);
+            }
+        }
+
+        let colors = self.colors.as_ref().map(|colors| colors.concat());
+
+        if !weld {
+            let mesh = Mesh::new_with_normals(vertices, normals, faces).with_uvs(uvs);
+            return match colors {
+                Some(colors) => mesh.with_colors(colors),
+                None => mesh,
+            };
+        }
+
+        let remap = weld_vertices(&vertices);
+        let kept: Vec<usize> = (0..vertices.len()).filter(|&i| remap[i] == i).collect();
+        let mut new_index = vec![0; vertices.len()];
+        for (k, &i) in kept.iter().enumerate() {
+            new_index[i] = k;
+        }
+        let index = |i: usize| new_index[remap[i]];
+
+        //normale moyenne des sommets fusionnés, sauf si elles s'annulent (couture d'une surface non orientable)
+        let mut summed = vec![Vector3::zeros(); vertices.len()];
+        for (i, normal) in normals.iter().enumerate() {
+            summed[remap[i]] += normal;
+        }
+        let welded_normals = kept
+            .iter()
+            .map(|&i| summed[i].try_normalize(1e-3).unwrap_or(normals[i]))
+            .collect();
+
+        let faces = faces
+            .into_iter()
+            .map(|face| face.map(index))
+            .filter(|&[a, b, c]| a != b && b != c && c != a)
+            .collect();
+
+        let mesh = Mesh::new_with_normals(kept.iter().map(|&i| vertices[i]).collect(), welded_normals, faces)
+            .with_uvs(kept.iter().map(|&i| uvs[i]).collect());
+
+        match colors {
+            Some(colors) => mesh.with_colors(kept.iter().map(|&i| colors[i]).collect()),
+            None => mesh,
+        }
+    }
+
+    //couleur du sommet d'indice index dans la grille du morceau part, 0 (couleur de l'objet) si la surface n'est pas colorée
+    fn vertex_color(&self, part: usize, index: usize) -> u32 {
+        self.colors.as_ref().map_or(0, |colors| colors[part][index])
+    }
+
+    pub fn colored_isolines(&self) -> impl Iterator<Item = ((Vector3<f32>, u32), (Vector3<f32>, u32))> + '_ {
+        self.parts.iter().enumerate().flat_map(move |(k, p)| {
+            let color = move |index| self.vertex_color(k, index);
+            p.isos_u_with(self.u_points_number, self.v_points_number, color)
+                .chain(p.isos_v_with(self.v_points_number, self.u_points_number, color))
+        })
+    }
+
+    pub fn colored_triangles(&self) -> impl Iterator<Item = ([Vector3<f32>; 3], [Vector3<f32>; 3], [u32; 3])> + '_ {
+        self.parts.iter().enumerate().flat_map(move |(k, p)| {
+            p.triangles_with(self.u_points_number, self.v_points_number, move |index| self.vertex_color(k, index))
+        })
+    }
+}
+
+//description textuelle d'une surface prédéfinie et de ses paramètres, de la forme "torus:r=30,r_rev=10,u=60,v=30"
+//les paramètres absents prennent leur valeur par défaut
+#[derive(Clone, Debug, PartialEq)]
+pub enum SurfaceSpec {
+    Sphere { r: f32, u: u32, v: u32 },
+    Torus { r: f32, r_rev: f32, u: u32, v: u32 },
+    Mobius { r: f32, w: f32, u: u32, v: u32 },
+    CubeSphere { r: f32, n: u32 },
+    Boy { scale: f32, u: u32, v: u32 },
+    Expression(ExpressionSurface),
+    Sweep(SweepSpec),
+}
+
+impl SurfaceSpec {
+    pub fn build(&self) -> Surface {
+        match *self {
+            SurfaceSpec::Sphere { r, u, v } => Surface::new_sphere(r, u, v),
+            SurfaceSpec::Torus { r, r_rev, u, v } => Surface::new_torus(r, r_rev, u, v),
+            SurfaceSpec::Mobius { r, w, u, v } => Surface::new_mobius(r, w, u, v),
+            SurfaceSpec::CubeSphere { r, n } => Surface::new_cube_sphere(r, n),
+            SurfaceSpec::Boy { scale, u, v } => Surface::new_boy(scale, u, v),
+            SurfaceSpec::Expression(ref surface) => Surface::new_from_param(surface.param(), surface.u, surface.v),
+            SurfaceSpec::Sweep(ref sweep) => sweep.build(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            SurfaceSpec::Sphere { .. } => "sphere",
+            SurfaceSpec::Torus { .. } => "torus",
+            SurfaceSpec::Mobius { .. } => "mobius",
+            SurfaceSpec::CubeSphere { .. } => "cube_sphere",
+            SurfaceSpec::Boy { .. } => "boy",
+            SurfaceSpec::Expression(_) => "expr",
+            SurfaceSpec::Sweep(sweep) => sweep.profile.name(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, f32)> {
+        match *self {
+            SurfaceSpec::Sphere { r, u, v } => vec![("r", r), ("u", u as f32), ("v", v as f32)],
+            SurfaceSpec::Torus { r, r_rev, u, v } => vec![("r", r), ("r_rev", r_rev), ("u", u as f32), ("v", v as f32)],
+            SurfaceSpec::Mobius { r, w, u, v } => vec![("r", r), ("w", w), ("u", u as f32), ("v", v as f32)],
+            SurfaceSpec::CubeSphere { r, n } => vec![("r", r), ("n", n as f32)],
+            SurfaceSpec::Boy { scale, u, v } => vec![("scale", scale), ("u", u as f32), ("v", v as f32)],
+            SurfaceSpec::Expression(_) | SurfaceSpec::Sweep(_) => vec![],
+        }
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        let number = || parse_number(key, value);
+        let count = || parse_count(key, value, 2);
+
+        match (self, key) {
+            (SurfaceSpec::Sphere { r, .. } | SurfaceSpec::Torus { r, .. } | SurfaceSpec::Mobius { r, .. } | SurfaceSpec::CubeSphere { r, .. }, "r") => *r = number()?,
+            (SurfaceSpec::Torus { r_rev, .. }, "r_rev") => *r_rev = number()?,
+            (SurfaceSpec::Mobius { w, .. }, "w") => *w = number()?,
+            (SurfaceSpec::Boy { scale, .. }, "scale") => *scale = number()?,
+            (SurfaceSpec::CubeSphere { n, .. }, "n") => *n = count()?,
+            (SurfaceSpec::Sphere { u, .. } | SurfaceSpec::Torus { u, .. } | SurfaceSpec::Mobius { u, .. } | SurfaceSpec::Boy { u, .. }, "u") => *u = count()?,
+            (SurfaceSpec::Sphere { v, .. } | SurfaceSpec::Torus { v, .. } | SurfaceSpec::Mobius { v, .. } | SurfaceSpec::Boy { v, .. }, "v") => *v = count()?,
+            (spec, _) => return Err(anyhow::anyhow!("Paramètre '{}' inconnu pour la surface '{}'", key, spec.name())),
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SurfaceSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (name, params) = s.split_once(':').unwrap_or((s, ""));
+
+        match name.trim() {
+            "expr" => return params.parse().map(SurfaceSpec::Expression),
+            "tube" => return SweepSpec::parse(Profile::Tube { r: 1.0 }, params).map(SurfaceSpec::Sweep),
+            "ribbon" => return SweepSpec::parse(Profile::Ribbon { w: 1.0 }, params).map(SurfaceSpec::Sweep),
+            _ => {}
+        }
+
+        let mut spec = match name.trim() {
+            "sphere" => SurfaceSpec::Sphere { r: 10.0, u: 40, v: 20 },
+            "torus" => SurfaceSpec::Torus { r: 10.0, r_rev: 4.0, u: 60, v: 30 },
+            "mobius" => SurfaceSpec::Mobius { r: 10.0, w: 3.0, u: 60, v: 10 },
+            "cube_sphere" => SurfaceSpec::CubeSphere { r: 10.0, n: 20 },
+            "boy" => SurfaceSpec::Boy { scale: 10.0, u: 60, v: 60 },
+            other => return Err(anyhow::anyhow!("Surface '{}' inconnue (sphere, torus, mobius, cube_sphere, boy, expr, tube ou ribbon)", other)),
+        };
+
+        for (key, value) in key_values(params, ',')? {
+            spec.set_param(key, value)?;
+        }
+
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for SurfaceSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SurfaceSpec::Expression(surface) => return write!(f, "expr:{surface}"),
+            SurfaceSpec::Sweep(sweep) => return write!(f, "{sweep}"),
+            _ => {}
+        }
+
+        let params = self.params().iter().map(|(k, v)| format!("{k}={v}")).join(",");
+        write!(f, "{}:{}", self.name(), params)
+    }
+}
+
+//profil balayé le long d'une courbe : un cercle (tube) ou un segment (ribbon)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Profile {
+    Tube { r: f32 },
+    Ribbon { w: f32 },
+}
+
+impl Profile {
+    fn name(&self) -> &'static str {
+        match self {
+            Profile::Tube { .. } => "tube",
+            Profile::Ribbon { .. } => "ribbon",
+        }
+    }
+}
+
+//surface balayée, les paramètres du profil puis la courbe après un '|' : "tube:r=1.5,v=12,frame=rmf|torus_knot:p=2,q=3"
+//w est la demi-largeur du ruban, comme pour mobius
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepSpec {
+    profile: Profile,
+    curve: CurveSpec,
+    v: u32,
+    frame: FrameKind,
+}
+
+impl SweepSpec {
+    pub fn build(&self) -> Surface {
+        let curve = self.curve.build();
+
+        match self.profile {
+            Profile::Tube { r } => Surface::new_tube(curve, r, self.v, self.frame),
+            Profile::Ribbon { w } => Surface::new_ribbon(curve, w, self.v, self.frame),
+        }
+    }
+
+    fn parse(mut profile: Profile, s: &str) -> anyhow::Result<Self> {
+        let (params, curve) = s
+            .split_once('|')
+            .ok_or_else(|| anyhow::anyhow!("Courbe manquante, '{}:paramètres|courbe' attendu", profile.name()))?;
+
+        let mut v = match profile {
+            Profile::Tube { .. } => 12,
+            Profile::Ribbon { .. } => 2,
+        };
+        let mut frame = FrameKind::default();
+
+        for (key, value) in key_values(params, ',')? {
+            match (&mut profile, key) {
+                (Profile::Tube { r }, "r") => *r = parse_number(key, value)?,
+                (Profile::Ribbon { w }, "w") => *w = parse_number(key, value)?,
+                (_, "v") => v = parse_count(key, value, 2)?,
+                (_, "frame") => frame = value.parse()?,
+                (_, other) => return Err(anyhow::anyhow!("Paramètre '{}' inconnu pour la surface '{}'", other, profile.name())),
+            }
+        }
+
+        Ok(Self { profile, curve: curve.parse()?, v, frame })
+    }
+}
+
+impl fmt::Display for SweepSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = match self.profile {
+            Profile::Tube { r } => format!("r={r}"),
+            Profile::Ribbon { w } => format!("w={w}"),
+        };
+
+        write!(f, "{}:{},v={},frame={}|{}", self.profile.name(), size, self.v, self.frame, self.curve)
+    }
+}
+
+//surface définie par des expressions de u et v, séparées par des ';' (les virgules servent aux arguments des fonctions) :
+//  "x=(2+cos(v))cos(u); y=(2+cos(v))sin(u); z=sin(v); u_range=0..2pi; v_range=0..2pi; u=60; v=30"
+//tout autre nom définit une constante utilisable dans les expressions suivantes ("a=2; x=a cos(u); ...")
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpressionSurface {
+    definitions: Vec<(String, String)>, //telles qu'écrites, pour l'affichage
+    x: Expression,
+    y: Expression,
+    z: Expression,
+    u_range: (f32, f32),
+    v_range: (f32, f32),
+    u: u32,
+    v: u32,
+}
+
+impl ExpressionSurface {
+    pub fn param(&self) -> SurfaceParam {
+        let (x, y, z) = (self.x.clone(), self.y.clone(), self.z.clone());
+        let f = move |u: f32, v: f32| Vector3::new(x.eval(&[u, v]), y.eval(&[u, v]), z.eval(&[u, v]));
+
+        SurfaceParam::new(Box::new(f), self.u_range, self.v_range)
+    }
+}
+
+//intervalle "a..b" dont les bornes sont des expressions constantes
+pub fn parse_range(value: &str, constants: &[(String, f32)]) -> anyhow::Result<(f32, f32)> {
+    let (a, b) = value.split_once("..").ok_or_else(|| anyhow::anyhow!("Intervalle '{}' invalide, 'a..b' attendu", value))?;
+    Ok((Expression::evaluate_constant(a, constants)?, Expression::evaluate_constant(b, constants)?))
+}
+
+pub fn parse_number(key: &str, value: &str) -> anyhow::Result<f32> {
+    value.trim().parse().map_err(|_| anyhow::anyhow!("Valeur '{}' invalide pour '{}'", value, key))
+}
+
+//entier >= min, éventuellement écrit comme un flottant ("40.0")
+pub fn parse_count(key: &str, value: &str, min: u32) -> anyhow::Result<u32> {
+    let value = parse_number(key, value)?;
+    match value >= min as f32 && value.fract() == 0.0 {
+        true => Ok(value as u32),
+        false => Err(anyhow::anyhow!("'{}' doit être un entier >= {}", key, min)),
+    }
+}
+
+//paires "nom=valeur" séparées par separator : ',' pour les paramètres des objets prédéfinis, ';' pour les définitions
+pub fn key_values(s: &str, separator: char) -> anyhow::Result<Vec<(&str, &str)>> {
+    s.split(separator)
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            p.split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| anyhow::anyhow!("'{}' invalide, 'nom=valeur' attendu", p))
+        })
+        .collect()
+}
+
+//définitions séparées par des ';' (ExpressionSurface, ExpressionCurve, ImplicitSpec), traitées dans l'ordre par define
+//qui renvoie false pour les noms qu'il ne connaît pas : ceux-ci définissent des constantes utilisables dans les définitions suivantes
+//renvoie les définitions telles qu'écrites, pour l'affichage
+pub fn parse_definitions(
+    s: &str,
+    mut define: impl FnMut(&str, &str, &[(String, f32)]) -> anyhow::Result<bool>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let definitions = key_values(s, ';')?;
+    let mut constants: Vec<(String, f32)> = Vec::new();
+
+    for &(key, value) in &definitions {
+        if !define(key, value, &constants)? {
+            let constant = Expression::evaluate_constant(value, &constants)?;
+            constants.push((key.to_string(), constant));
+        }
+    }
+
+    Ok(definitions.into_iter().map(|(key, value)| (key.to_string(), value.to_string())).collect())
+}
+
+impl FromStr for ExpressionSurface {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (mut x, mut y, mut z) = (None, None, None);
+        let (mut u_range, mut v_range) = ((0.0, 1.0), (0.0, 1.0));
+        let (mut u, mut v) = (40, 40);
+
+        let definitions = parse_definitions(s, |key, value, constants| {
+            let expression = || Expression::compile(value, &["u", "v"], constants);
+
+            match key {
+                "x" => x = Some(expression()?),
+                "y" => y = Some(expression()?),
+                "z" => z = Some(expression()?),
+                "u_range" => u_range = parse_range(value, constants)?,
+                "v_range" => v_range = parse_range(value, constants)?,
+                "u" => u = parse_count(key, value, 2)?,
+                "v" => v = parse_count(key, value, 2)?,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+
+        let missing = |name: &str| anyhow::anyhow!("'{}' manquant dans la surface", name);
+
+        Ok(Self {
+            x: x.ok_or_else(|| missing("x"))?,
+            y: y.ok_or_else(|| missing("y"))?,
+            z: z.ok_or_else(|| missing("z"))?,
+            definitions,
+            u_range,
+            v_range,
+            u,
+            v,
+        })
+    }
+}
+
+impl fmt::Display for ExpressionSurface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let definitions = self.definitions.iter().map(|(k, v)| format!("{k}={v}")).join("; ");
+        write!(f, "{definitions}")
+    }
 }
 
-#[derive(Constructor)]
 pub struct SurfaceParam {
     f: Box<dyn Fn(f32, f32) -> Vector3<f32>>,
     u_range: (f32, f32),
     v_range: (f32, f32),
+    partials: Option<Box<dyn Fn(f32, f32) -> Partials>>, //dérivées analytiques, sinon differences finies
 }
 
 impl SurfaceParam {
+    pub fn new(f: Box<dyn Fn(f32, f32) -> Vector3<f32>>, u_range: (f32, f32), v_range: (f32, f32)) -> Self {
+        Self { f, u_range, v_range, partials: None }
+    }
+
+    pub fn with_partials(mut self, partials: Box<dyn Fn(f32, f32) -> Partials>) -> Self {
+        self.partials = Some(partials);
+        self
+    }
+
     //sphere de rayon r
     pub fn new_sphere(r: f32) -> Self {
         let f = move |u: f32, v: f32| {
@@ -90,7 +550,20 @@ impl SurfaceParam {
             Vector3::new(x, y, z)
         };
 
-        SurfaceParam::new(Box::new(f), (-PI, PI), (-FRAC_PI_2, FRAC_PI_2))
+        let partials = move |u: f32, v: f32| {
+            let (sin_u, cos_u) = u.sin_cos();
+            let (sin_v, cos_v) = v.sin_cos();
+
+            Partials::new(
+                r * Vector3::new(-sin_u * cos_v, cos_u * cos_v, 0.0),
+                r * Vector3::new(-cos_u * sin_v, -sin_u * sin_v, cos_v),
+                r * Vector3::new(-cos_u * cos_v, -sin_u * cos_v, 0.0),
+                r * Vector3::new(sin_u * sin_v, -cos_u * sin_v, 0.0),
+                r * Vector3::new(-cos_u * cos_v, -sin_u * cos_v, -sin_v),
+            )
+        };
+
+        SurfaceParam::new(Box::new(f), (-PI, PI), (-FRAC_PI_2, FRAC_PI_2)).with_partials(Box::new(partials))
     }
 
     pub fn new_torus(r: f32, r_rev: f32) -> Self {
@@ -107,7 +580,21 @@ impl SurfaceParam {
             Vector3::new(x, y, z)
         };
     
-        SurfaceParam::new(Box::new(f), (0.0, 2.0 * PI), (0.0, 2.0 * PI))
+        let partials = move |u: f32, v: f32| {
+            let (sin_u, cos_u) = u.sin_cos();
+            let (sin_v, cos_v) = v.sin_cos();
+            let radius = r + r_rev * cos_v;
+
+            Partials::new(
+                Vector3::new(-radius * sin_u, radius * cos_u, 0.0),
+                r_rev * Vector3::new(-sin_v * cos_u, -sin_v * sin_u, cos_v),
+                Vector3::new(-radius * cos_u, -radius * sin_u, 0.0),
+                r_rev * Vector3::new(sin_v * sin_u, -sin_v * cos_u, 0.0),
+                r_rev * Vector3::new(-cos_v * cos_u, -cos_v * sin_u, -sin_v),
+            )
+        };
+
+        SurfaceParam::new(Box::new(f), (0.0, 2.0 * PI), (0.0, 2.0 * PI)).with_partials(Box::new(partials))
     }
 
     pub fn new_mobius(r: f32, w: f32) -> Self {
@@ -152,6 +639,32 @@ impl SurfaceParam {
         SurfaceParam::new(Box::new(f), (-1.0, 1.0), (-1.0, 1.0))
     }
 
+    //u parcourt la courbe, v fait le tour du cercle de rayon r dans le plan normal
+    pub fn new_tube(curve: Curve, r: f32, frame: FrameKind) -> Self {
+        let u_range = curve.t_range();
+        let moving_frame = curve.into_moving_frame(frame);
+
+        let f = move |u: f32, v: f32| {
+            let (p, frame) = moving_frame(u);
+            p + r * (v.cos() * frame.normal + v.sin() * frame.binormal)
+        };
+
+        SurfaceParam::new(Box::new(f), u_range, (0.0, 2.0 * PI))
+    }
+
+    //u parcourt la courbe, v le segment [-w, w] porté par la normale
+    pub fn new_ribbon(curve: Curve, w: f32, frame: FrameKind) -> Self {
+        let u_range = curve.t_range();
+        let moving_frame = curve.into_moving_frame(frame);
+
+        let f = move |u: f32, v: f32| {
+            let (p, frame) = moving_frame(u);
+            p + v * frame.normal
+        };
+
+        SurfaceParam::new(Box::new(f), u_range, (-w, w))
+    }
+
     pub fn new_boy(scale: f32) -> Self {
         let f = move |u: f32, v: f32| {
             let su = u.sin();
@@ -178,42 +691,182 @@ impl SurfaceParam {
         )
     }
     
+    //valeurs de u et de v échantillonnées pour les isolignes et les triangles, le sommet (u_i, v_j) a l'indice i * nv + j
+    pub fn grid(&self, u_points_number: u32, v_points_number: u32) -> (Vec<f32>, Vec<f32>) {
+        (
+            regular_sample(self.u_range.0, self.u_range.1, u_points_number).collect(),
+            regular_sample(self.v_range.0, self.v_range.1, v_points_number).collect(),
+        )
+    }
+
     //segments dans tout l'intervalle sous-echantilloné des u pour chaque valeurs du sous echantillonage des v
     pub fn isos_v(
         &self,
         v_points_number: u32,
         u_points_number: u32,
     ) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> {
-        let v_values = regular_sample(self.v_range.0, self.v_range.1, v_points_number);
-        let u_values: Vec<f32> =
-            regular_sample(self.u_range.0, self.u_range.1, u_points_number).collect();
-        v_values.flat_map(move |v| {
+        self.isos_v_with(v_points_number, u_points_number, |_| ()).map(|((p0, _), (p1, _))| (p0, p1))
+    }
+
+    //segments dans tout l'intervalle sous-echantilloné des v pour chaque valeurs du sous echantillonage des u
+    pub fn isos_u(
+        &self,
+        u_points_number: u32,
+        v_points_number: u32,
+    ) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> {
+        self.isos_u_with(u_points_number, v_points_number, |_| ()).map(|((p0, _), (p1, _))| (p0, p1))
+    }
+
+    //comme isos_v, chaque sommet porte en plus l'attribut de son indice dans la grille
+    pub fn isos_v_with<T: Clone>(
+        &self,
+        v_points_number: u32,
+        u_points_number: u32,
+        attribute: impl Fn(usize) -> T + Copy,
+    ) -> impl Iterator<Item = ((Vector3<f32>, T), (Vector3<f32>, T))> {
+        let (u_values, v_values) = self.grid(u_points_number, v_points_number);
+        let nv = v_values.len();
+
+        v_values.into_iter().enumerate().flat_map(move |(j, v)| {
             u_values
                 .clone()
                 .into_iter()
-                .map(move |u| (self.f)(u, v))
+                .enumerate()
+                .map(move |(i, u)| ((self.f)(u, v), attribute(i * nv + j)))
                 .tuple_windows()
         })
     }
 
-    //segments dans tout l'intervalle sous-echantilloné des v pour chaque valeurs du sous echantillonage des u
-    pub fn isos_u(
+    pub fn isos_u_with<T: Clone>(
         &self,
         u_points_number: u32,
         v_points_number: u32,
-    ) -> impl Iterator<Item = (Vector3<f32>, Vector3<f32>)> {
-        let u_values = regular_sample(self.u_range.0, self.u_range.1, u_points_number);
-        let v_values: Vec<f32> =
-            regular_sample(self.v_range.0, self.v_range.1, v_points_number).collect();
-        u_values.flat_map(move |u| {
+        attribute: impl Fn(usize) -> T + Copy,
+    ) -> impl Iterator<Item = ((Vector3<f32>, T), (Vector3<f32>, T))> {
+        let (u_values, v_values) = self.grid(u_points_number, v_points_number);
+        let nv = v_values.len();
+
+        u_values.into_iter().enumerate().flat_map(move |(i, u)| {
             v_values
                 .clone()
                 .into_iter()
-                .map(move |v| (self.f)(u, v))
+                .enumerate()
+                .map(move |(j, v)| ((self.f)(u, v), attribute(i * nv + j)))
                 .tuple_windows()
         })
     }
 
+    pub fn point(&self, u: f32, v: f32) -> Vector3<f32> {
+        (self.f)(u, v)
+    }
+
+    pub fn u_range(&self) -> (f32, f32) {
+        self.u_range
+    }
+
+    pub fn v_range(&self) -> (f32, f32) {
+        self.v_range
+    }
+
+    //dérivées premières seules, par differences finies centrées si elles ne sont pas fournies
+    pub fn tangents(&self, u: f32, v: f32) -> (Vector3<f32>, Vector3<f32>) {
+        if let Some(partials) = &self.partials {
+            let partials = partials(u, v);
+            return (partials.fu, partials.fv);
+        }
+
+        let hu = (self.u_range.1 - self.u_range.0) * 1e-3;
+        let hv = (self.v_range.1 - self.v_range.0) * 1e-3;
+
+        let fu = ((self.f)(u + hu, v) - (self.f)(u - hu, v)) / (2.0 * hu);
+        let fv = ((self.f)(u, v + hv) - (self.f)(u, v - hv)) / (2.0 * hv);
+
+        (fu, fv)
+    }
+
+    //dérivées premières et secondes, par differences finies centrées si elles ne sont pas fournies
+    //le pas des dérivées secondes est plus grand pour limiter les erreurs d'arrondi en f32
+    pub fn partials(&self, u: f32, v: f32) -> Partials {
+        if let Some(partials) = &self.partials {
+            return partials(u, v);
+        }
+
+        let (fu, fv) = self.tangents(u, v);
+        let hu = (self.u_range.1 - self.u_range.0) * 1e-2;
+        let hv = (self.v_range.1 - self.v_range.0) * 1e-2;
+        let f = |du: f32, dv: f32| (self.f)(u + du, v + dv);
+
+        let fuu = (f(hu, 0.0) - 2.0 * f(0.0, 0.0) + f(-hu, 0.0)) / (hu * hu);
+        let fvv = (f(0.0, hv) - 2.0 * f(0.0, 0.0) + f(0.0, -hv)) / (hv * hv);
+        let fuv = (f(hu, hv) - f(hu, -hv) - f(-hu, hv) + f(-hu, -hv)) / (4.0 * hu * hv);
+
+        Partials::new(fu, fv, fuu, fuv, fvv)
+    }
+
+    //normale unitaire en (u, v), nulle si le paramètrage est dégénéré en ce point
+    pub fn normal(&self, u: f32, v: f32) -> Vector3<f32> {
+        let (fu, fv) = self.tangents(u, v);
+        fu.cross(&fv).try_normalize(f32::EPSILON).unwrap_or_else(Vector3::zeros)
+    }
+
+    pub fn first_fundamental_form(&self, u: f32, v: f32) -> QuadraticForm {
+        self.partials(u, v).first_fundamental_form()
+    }
+
+    pub fn second_fundamental_form(&self, u: f32, v: f32) -> Option<QuadraticForm> {
+        self.partials(u, v).second_fundamental_form()
+    }
+
+    pub fn gaussian_curvature(&self, u: f32, v: f32) -> Option<f32> {
+        self.partials(u, v).gaussian_curvature()
+    }
+
+    pub fn mean_curvature(&self, u: f32, v: f32) -> Option<f32> {
+        self.partials(u, v).mean_curvature()
+    }
+
+    pub fn principal_curvatures(&self, u: f32, v: f32) -> Option<PrincipalCurvatures> {
+        self.partials(u, v).principal_curvatures()
+    }
+
+    //deux triangles par cellule de la grille (u, v) echantillonée comme pour les isolignes, avec les normales aux sommets
+    pub fn triangles(
+        &self,
+        u_points_number: u32,
+        v_points_number: u32,
+    ) -> impl Iterator<Item = ([Vector3<f32>; 3], [Vector3<f32>; 3])> + '_ {
+        self.triangles_with(u_points_number, v_points_number, |_| ()).map(|(positions, normals, _)| (positions, normals))
+    }
+
+    pub fn triangles_with<'a, T: Copy + 'a>(
+        &'a self,
+        u_points_number: u32,
+        v_points_number: u32,
+        attribute: impl Fn(usize) -> T,
+    ) -> impl Iterator<Item = ([Vector3<f32>; 3], [Vector3<f32>; 3], [T; 3])> + 'a {
+        let (u_values, v_values) = self.grid(u_points_number, v_points_number);
+        let nv = v_values.len();
+
+        let grid: Vec<(Vector3<f32>, Vector3<f32>, T)> = u_values
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &u)| v_values.iter().enumerate().map(move |(j, &v)| (i * nv + j, u, v)))
+            .map(|(index, u, v)| ((self.f)(u, v), self.normal(u, v), attribute(index)))
+            .collect();
+
+        let cells = (0..u_values.len().saturating_sub(1)).cartesian_product(0..nv.saturating_sub(1));
+
+        cells.flat_map(move |(i, j)| {
+            let corner = |di: usize, dj: usize| grid[(i + di) * nv + j + dj];
+            let (a, b, c, d) = (corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1));
+
+            [
+                ([a.0, b.0, c.0], [a.1, b.1, c.1], [a.2, b.2, c.2]),
+                ([a.0, c.0, d.0], [a.1, c.1, d.1], [a.2, c.2, d.2]),
+            ]
+        })
+    }
+
     //isos_u U isos_v -> the wireframe
     pub fn isolines(
         &self,
@@ -225,6 +878,40 @@ impl SurfaceParam {
     }
 }
 
+//pour chaque sommet, l'indice du premier sommet confondu avec lui (à une fraction de la taille de l'objet près)
+//les positions sont rangées dans une grille de cellules de la taille de la tolérance, on cherche dans les cellules voisines
+fn weld_vertices(vertices: &[Vector3<f32>]) -> Vec<usize> {
+    let (min, max) = vertices.iter().fold(
+        (Vector3::repeat(f32::INFINITY), Vector3::repeat(f32::NEG_INFINITY)),
+        |(min, max), p| (min.inf(p), max.sup(p)),
+    );
+    let tolerance = ((max - min).norm() * 1e-5).max(f32::EPSILON);
+    let cell = |p: &Vector3<f32>| p.map(|x| (x / tolerance).floor() as i64);
+
+    let mut cells: HashMap<Vector3<i64>, Vec<usize>> = HashMap::new();
+    let mut remap = Vec::with_capacity(vertices.len());
+
+    for (i, p) in vertices.iter().enumerate() {
+        let c = cell(p);
+        let neighbours = (-1..=1).cartesian_product(-1..=1).cartesian_product(-1..=1).map(|((x, y), z)| c + Vector3::new(x, y, z));
+        let existing = neighbours
+            .filter_map(|n| cells.get(&n))
+            .flatten()
+            .copied()
+            .find(|&j| (vertices[j] - p).norm() <= tolerance);
+
+        match existing {
+            Some(j) => remap.push(j),
+            None => {
+                cells.entry(c).or_default().push(i);
+                remap.push(i);
+            }
+        }
+    }
+
+    remap
+}
+
 //sous echantillonage regulier de [a; b] en n points
 fn regular_sample(a: f32, b: f32, n: u32) -> impl Iterator<Item = f32> {
     (0..n).map(move |i| a + (b - a) * i as f32 / (n - 1) as f32)
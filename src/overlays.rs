@@ -0,0 +1,127 @@
+use derive_more::Constructor;
+use nalgebra::Vector3;
+
+use crate::{EPSILON, camera::{Camera, Point2f}, drawing::{FrameBuffer, draw_primitive, projected_to_pixel, text::{GLYPH_SIZE, draw_text}}, objects::{Line3f, Point3f, Primitive3f}, render::primitives_to_screen_primitives, world::World};
+
+pub const X_AXIS_COLOR: u32 = 0xff4040;
+pub const Y_AXIS_COLOR: u32 = 0x40ff40;
+pub const Z_AXIS_COLOR: u32 = 0x4080ff;
+const GRID_COLOR: u32 = 0x505050;
+const MAX_GRID_LINES: i32 = 100; //de chaque côté de l'origine, pour un pas très petit devant la scène
+
+const GIZMO_SIZE: usize = 90;
+const GIZMO_MARGIN: usize = 8;
+const GIZMO_DISTANCE: f32 = 2.6; //distance de la camera du gizmo à son centre, les axes mesurant 1
+
+//repères dessinés par dessus la scène, chacun activable séparément
+#[derive(Constructor, Clone, Copy, Debug, Default)]
+pub struct Overlays {
+    pub axes: bool,  //axes X, Y, Z du monde à l'origine
+    pub grid: bool,  //grille dans le plan z = 0 (z est la hauteur, comme pour --color-by height)
+    pub gizmo: bool, //axes du monde vus sous l'orientation de la camera, en bas à gauche de l'écran
+    pub grid_spacing: Option<f32>, //sinon choisi d'après la taille de la scène
+}
+
+//demi-côté de la grille et longueur des axes : de quoi couvrir les objets visibles autour de l'origine
+fn reference_extent(world: &World) -> f32 {
+    world.visible_bounding_sphere().map_or(1.0, |sphere| sphere.center.norm() + sphere.radius).max(EPSILON)
+}
+
+//pas "rond" (1, 2 ou 5 fois une puissance de 10) donnant une dizaine de cases de chaque côté de l'origine
+pub fn nice_spacing(extent: f32) -> f32 {
+    let raw = extent / 10.0;
+    let power = 10f32.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0].into_iter().map(|m| m * power).find(|&spacing| spacing >= raw).unwrap_or(10.0 * power)
+}
+
+//pixel d'un point exprimé dans le repère de la camera, dans une image carrée de côté size
+fn project_point(p: Vector3<f32>, f: f32, size: usize) -> (i32, i32) {
+    let projected = Point2f::new((f * p.x / p.z, f * p.y / p.z), p.z, 0);
+    projected_to_pixel(projected, size as i32, size as i32).position()
+}
+
+fn segment(p0: Vector3<f32>, p1: Vector3<f32>, color: u32) -> Primitive3f {
+    Primitive3f::Line(Line3f::new(Point3f::new(p0, color), Point3f::new(p1, color)))
+}
+
+fn axes_primitives(length: f32) -> impl Iterator<Item = Primitive3f> {
+    [(Vector3::x(), X_AXIS_COLOR), (Vector3::y(), Y_AXIS_COLOR), (Vector3::z(), Z_AXIS_COLOR)]
+        .into_iter()
+        .map(move |(axis, color)| segment(Vector3::zeros(), length * axis, color))
+}
+
+//nombre de cases de chaque côté de l'origine pour que la grille contienne extent
+fn grid_cells(extent: f32, spacing: f32) -> i32 {
+    ((extent / spacing).ceil() as i32).clamp(1, MAX_GRID_LINES)
+}
+
+//lignes parallèles aux axes X et Y, tous les spacing, sur un carré de 2n cases centré sur l'origine
+//avec les axes, les lignes qui passent par l'origine s'arrêtent à celle-ci pour ne pas recouvrir les demi-axes positifs
+fn grid_primitives(n: i32, spacing: f32, axes: bool) -> impl Iterator<Item = Primitive3f> {
+    let half = n as f32 * spacing;
+
+    (-n..=n).flat_map(move |k| {
+        let t = k as f32 * spacing;
+        let end = if axes && k == 0 { 0.0 } else { half };
+        [
+            segment(Vector3::new(t, -half, 0.0), Vector3::new(t, end, 0.0), GRID_COLOR),
+            segment(Vector3::new(-half, t, 0.0), Vector3::new(end, t, 0.0), GRID_COLOR),
+        ]
+    })
+}
+
+//après le rendu de la scène : les axes et la grille passent par le même pipeline et le même z-buffer que les objets, qui peuvent donc les cacher
+//avec la grille, les axes vont jusqu'à son bord
+pub fn draw_overlays(frame: &mut FrameBuffer, world: &World, camera: &Camera, overlays: &Overlays) {
+    let extent = reference_extent(world);
+    let spacing = overlays.grid_spacing.unwrap_or_else(|| nice_spacing(extent));
+
+    let n = grid_cells(extent, spacing);
+    let length = if overlays.grid { n as f32 * spacing } else { extent };
+
+    let axes = overlays.axes.then(|| axes_primitives(length)).into_iter().flatten();
+    let grid = overlays.grid.then(|| grid_primitives(n, spacing, overlays.axes)).into_iter().flatten();
+
+    let (width, height) = (frame.width, frame.height);
+    for primitive in primitives_to_screen_primitives(axes.chain(grid), camera, width, height) {
+        draw_primitive(&primitive, frame);
+    }
+
+    if overlays.gizmo {
+        draw_gizmo(frame, camera);
+    }
+}
+
+//axes unité vus par une camera de même orientation que celle de la scène, rendus dans une petite image puis recopiés dans le coin
+fn draw_gizmo(frame: &mut FrameBuffer, camera: &Camera) {
+    if frame.width < GIZMO_SIZE + GIZMO_MARGIN || frame.height < GIZMO_SIZE + GIZMO_MARGIN {
+        return;
+    }
+
+    let mut pose = camera.cam_to_world();
+    pose.fixed_view_mut::<3, 1>(0, 3).copy_from(&(-GIZMO_DISTANCE * camera.forward()));
+    let gizmo_camera = Camera::new_from_pose(camera.fovy(), pose);
+
+    let mut gizmo = FrameBuffer::new(GIZMO_SIZE, GIZMO_SIZE);
+    for primitive in primitives_to_screen_primitives(axes_primitives(1.0), &gizmo_camera, GIZMO_SIZE, GIZMO_SIZE) {
+        draw_primitive(&primitive, &mut gizmo);
+    }
+
+    //nom de chaque axe un peu au delà de son extrémité, toujours devant la camera du gizmo
+    for (axis, label, color) in [(Vector3::<f32>::x(), "X", X_AXIS_COLOR), (Vector3::y(), "Y", Y_AXIS_COLOR), (Vector3::z(), "Z", Z_AXIS_COLOR)] {
+        let tip = gizmo_camera.world_to_camera_coordinates(&(1.3 * axis));
+        let (x, y) = project_point(tip, gizmo_camera.perspective_center_distance, GIZMO_SIZE);
+        let half = GLYPH_SIZE as i32 / 2;
+        draw_text(&mut gizmo, (x - half).max(0) as usize, (y - half).max(0) as usize, label, color);
+    }
+
+    let (x0, y0) = (GIZMO_MARGIN, frame.height - GIZMO_MARGIN - GIZMO_SIZE);
+    for y in 0..GIZMO_SIZE {
+        for x in 0..GIZMO_SIZE {
+            let color = gizmo.color[y * GIZMO_SIZE + x];
+            if color != 0 {
+                frame.color[(y0 + y) * frame.width + x0 + x] = color;
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::{ops::Range, path::Path};
+
+use anyhow::Result;
+use derive_more::Constructor;
+use nalgebra::Matrix4;
+use rayon::prelude::*;
+
+use crate::{camera::{Camera, Point2f, project_primitive}, drawing::{FrameBand, FrameBuffer, Point2i, Primitive2i, colormap::draw_colorbar, draw_point, draw_primitive_in_band, image::save_image, projected_primitive_to_screen_primitive, projected_to_pixel, shading::{DirectionalLight, RenderMode, shade_primitive}}, objects::{Primitive3f, bounds::BoundingSphere, pointclouds::{DEFAULT_POINT_COLOR, PointCloud}}, world::World};
+
+//réglages du rendu communs à tous les objets de la scène
+#[derive(Constructor, Clone, Copy, Default)]
+pub struct RenderSettings {
+    pub mode: RenderMode,
+    pub light: DirectionalLight,
+}
+
+//transformation, découpe par le volume de vision, projection puis passage en pixels de primitives exprimées dans le repère du monde
+pub fn primitives_to_screen_primitives(primitives: impl Iterator<Item = Primitive3f>, camera: &Camera, width: usize, height: usize) -> impl Iterator<Item = Primitive2i> {
+    let projector = camera.projector();
+    let camera_primitives = primitives.map(move |primitive| camera.world_primitive_to_camera_coordinates(&primitive));
+    let frustum = camera.frustum(width as f32 / height as f32);
+    let camera_visible_primitives = camera_primitives.flat_map(move |primitive| frustum.clip_primitive(primitive));
+    let camera_projected_primitives = camera_visible_primitives.map(move |primitive| project_primitive(primitive, &projector));
+    camera_projected_primitives.map(move |projected| projected_primitive_to_screen_primitive(projected, width, height))
+}
+
+//nombre de primitives traitées ensemble : assez pour occuper tous les threads, assez peu pour ne pas garder en mémoire toutes les primitives projetées d'un gros objet
+const BATCH_SIZE: usize = 1 << 15;
+
+//les points d'un nuage sont plus légers à traiter que les primitives, on en prend davantage à la fois
+const POINT_BATCH_SIZE: usize = 1 << 18;
+
+//chemin direct pour les points d'un nuage : les colonnes sont lues sans construire de Primitive3f, avec une seule transformation affine vers la camera
+//un point sans couleur prend celle de l'objet, comme pour les primitives
+fn point_cloud_to_screen_points(cloud: &PointCloud, range: Range<usize>, color: u32, to_camera: &Matrix4<f32>, camera: &Camera, width: usize, height: usize) -> Vec<Point2i> {
+    let (projector, frustum) = (camera.projector(), camera.frustum(width as f32 / height as f32));
+    let rotation = to_camera.fixed_view::<3, 3>(0, 0).into_owned();
+    let translation = to_camera.fixed_view::<3, 1>(0, 3).into_owned();
+
+    cloud.positions()[range.clone()]
+        .par_iter()
+        .zip(range)
+        .filter_map(|(p, i)| {
+            let p = rotation * p + translation;
+            if !frustum.contains(&p) {
+                return None;
+            }
+
+            let point_color = match cloud.color(i) {
+                DEFAULT_POINT_COLOR => color,
+                point_color => point_color,
+            };
+            Some(projected_to_pixel(Point2f::new(projector.project(&p), projector.depth(p.z), point_color), width as i32, height as i32))
+        })
+        .collect()
+}
+
+//nombre de primitives réparties dans les bandes par une même tâche
+const BUCKET_CHUNK_SIZE: usize = 1 << 12;
+
+//répartit en parallèle les primitives entre les bandes de rows lignes qu'elles touchent, rows(item) donnant leurs première et dernière lignes
+//le résultat est indexé par morceau du lot puis par bande, parcourir les morceaux dans l'ordre donne les primitives d'une bande dans l'ordre du lot
+fn bucket_by_band<T: Sync>(items: &[T], rows: impl Fn(&T) -> (i32, i32) + Sync, band_rows: usize, band_count: usize) -> Vec<Vec<Vec<usize>>> {
+    items
+        .par_chunks(BUCKET_CHUNK_SIZE)
+        .enumerate()
+        .map(|(chunk_index, chunk)| {
+            let mut buckets = vec![Vec::new(); band_count];
+            for (i, item) in chunk.iter().enumerate() {
+                let (y_min, y_max) = rows(item);
+                if y_max < 0 {
+                    continue;
+                }
+
+                let first = y_min.max(0) as usize / band_rows;
+                let last = (y_max as usize / band_rows).min(band_count - 1);
+                for bucket in buckets.iter_mut().take(last + 1).skip(first) {
+                    bucket.push(chunk_index * BUCKET_CHUNK_SIZE + i);
+                }
+            }
+            buckets
+        })
+        .collect()
+}
+
+//dessine chaque élément dans les seules bandes qu'il touche, une bande par tâche
+fn draw_in_bands<T: Sync>(bands: &mut [FrameBand], items: &[T], rows: impl Fn(&T) -> (i32, i32) + Sync, band_rows: usize, draw: impl Fn(&T, &mut FrameBand) + Sync) {
+    let buckets = bucket_by_band(items, rows, band_rows, bands.len());
+
+    bands.par_iter_mut().enumerate().for_each(|(b, band)| {
+        buckets.iter().flat_map(|chunk| &chunk[b]).for_each(|&i| draw(&items[i], band));
+    });
+}
+
+//efface le buffer et y dessine tous les objets visibles de la scène, retourne le nombre de primitives dessinées
+//les objets entièrement hors du champ de la camera sont écartés d'après leur sphère englobante, avant de générer leurs primitives
+//les primitives de chaque objet, générées une fois et gardées par son noeud, sont transformées, éclairées, découpées et projetées en parallèle, par lots
+//chaque lot est ensuite réparti entre des bandes horizontales de l'écran, rasterisées en parallèle : une bande ne dessine que ses primitives, dans l'ordre du lot, l'image est donc la même qu'en séquentiel
+//la barre de couleurs d'une surface colorée par un champ est dessinée par dessus
+pub fn render_frame(world: &World, camera: &Camera, settings: &RenderSettings, frame: &mut FrameBuffer) -> usize {
+    frame.clear();
+
+    let (width, height) = (frame.width, frame.height);
+    if width == 0 || height == 0 {
+        return 0;
+    }
+    let (projector, frustum) = (camera.projector(), camera.frustum(width as f32 / height as f32));
+    let in_view = |sphere: &BoundingSphere| frustum.intersects_sphere(&camera.world_to_camera_coordinates(&sphere.center), sphere.radius);
+
+    let rows = height.div_ceil(4 * rayon::current_num_threads());
+    let mut bands = frame.bands(rows);
+    let mut count = 0;
+
+    for (id, node) in world.visible_nodes_where(in_view) {
+        let local_to_world = world.local_to_world(id);
+
+        if let Some(cloud) = node.object().point_cloud() {
+            let to_camera = camera.world_to_cam() * local_to_world;
+            for start in (0..cloud.len()).step_by(POINT_BATCH_SIZE) {
+                let range = start..(start + POINT_BATCH_SIZE).min(cloud.len());
+                let points = point_cloud_to_screen_points(cloud, range, node.object().color(), &to_camera, camera, width, height);
+
+                draw_in_bands(&mut bands, &points, |point| (point.position().1, point.position().1), rows, |point, band| draw_point(band, point));
+                count += points.len();
+            }
+            continue;
+        }
+
+        for batch in node.raw_primitives(settings.mode).chunks(BATCH_SIZE) {
+            let screen_primitives: Vec<Primitive2i> = batch
+                .par_iter()
+                .cloned()
+                .map(|primitive| match settings.mode {
+                    RenderMode::Wireframe => primitive.transformed(local_to_world),
+                    RenderMode::Flat | RenderMode::Gouraud => shade_primitive(primitive.transformed(local_to_world), &settings.light, settings.mode),
+                })
+                .flat_map_iter(|primitive| frustum.clip_primitive(camera.world_primitive_to_camera_coordinates(&primitive)))
+                .map(|primitive| projected_primitive_to_screen_primitive(project_primitive(primitive, &projector), width, height))
+                .collect();
+
+            draw_in_bands(&mut bands, &screen_primitives, Primitive2i::rows, rows, draw_primitive_in_band);
+            count += screen_primitives.len();
+        }
+    }
+    drop(bands);
+
+    if let Some(legend) = world.legend() {
+        draw_colorbar(frame, legend);
+    }
+
+    count
+}
+
+//rendu sans fenêtre, dans un buffer alloué pour l'occasion
+pub fn render_offscreen(world: &World, camera: &Camera, settings: &RenderSettings, width: usize, height: usize) -> FrameBuffer {
+    let mut frame = FrameBuffer::new(width, height);
+    render_frame(world, camera, settings, &mut frame);
+    frame
+}
+
+//rendu sans fenêtre puis écriture de l'image, le format (png ou ppm) est déduit de l'extension
+pub fn render_to_file(world: &World, camera: &Camera, settings: &RenderSettings, width: usize, height: usize, path: &Path) -> Result<()> {
+    let frame = render_offscreen(world, camera, settings, width, height);
+    save_image(&frame, path)
+}
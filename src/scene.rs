@@ -0,0 +1,422 @@
+use std::{f32::consts::PI, fmt::{self, Write as _}, fs, path::Path};
+
+use anyhow::{Context, Result};
+use nalgebra::{Matrix4, Vector3};
+
+use crate::{DEFAULT_FAR, EPSILON, camera::{Camera, Projection, UpAxis}, drawing::{colormap::Colormap, shading::DirectionalLight}, objects::{Object, ObjectSource, coloring::SurfaceColoring}, render::RenderSettings, world::World};
+
+//fichier de scène textuel, une directive par ligne sous la forme "mot-clé clé=valeur ...", les lignes commençant par '#' sont des commentaires :
+//
+//  render mode=gouraud light=-1,-1,-1 ambient=0.15
+//  camera fovy=1.047 far=10000 up=z projection=ortho focus=100 pose=-1,0,0,0,0,0,1,-100,0,1,0,0
+//  object name="tore" surface=torus:r=30,r_rev=10 color=#ff8800
+//  object name="lune" parent="tore" surface=sphere:r=3 translate=40,0,0
+//  object name="jardin" cloud=jardin.ply visible=false
+//  object name="statue" mesh=scans/statue.stl color=#d0c0a0
+//  object name="hélicoïde" surface="expr:x=v cos(u); y=v sin(u); z=2u; u_range=0..4pi; v_range=-5..5"
+//  object name="gyroïde" implicit="gyroid; scale=3; n=60"
+//  object name="noeud" curve=torus_knot:p=2,q=3 color=#ffcc00
+//  object name="géodésiques" geodesic="u=0,v=1,fan=12,length=80|torus:r=30,r_rev=10" color=#ffff00
+//  object name="selle" surface="expr:x=u; y=v; z=u v/5; u_range=-5..5; v_range=-5..5" color_by=gaussian colormap=diverging range=-0.05,0.05
+//
+//les transformations (pose de la camera, transform des objets) sont les trois premières lignes de la matrice, par lignes
+//up (optionnel) est l'axe du monde que la camera garde vertical
+//projection vaut perspective (par défaut) ou ortho, focus est la distance du plan cadré, dont la hauteur vue en orthographique est déduite (par défaut, celui qui passe par l'origine)
+//les noms des objets sont uniques, parent désigne un objet déclaré plus haut
+//un objet peut aussi être placé avec translate=x,y,z et rotate_x/rotate_y/rotate_z=angle (radians), appliqués dans l'ordre
+//les chemins des nuages et des maillages sont relatifs au fichier de scène
+//dans une valeur entre guillemets, \" est un guillemet et \\ une barre oblique inverse
+//une surface peut être colorée par un champ scalaire (color_by), colormap et range (min,max) sont alors optionnels
+pub struct Scene {
+    pub world: World,
+    pub camera: Option<Camera>,
+    pub settings: RenderSettings,
+}
+
+pub fn load_scene(path: &Path) -> Result<Scene> {
+    let text = fs::read_to_string(path).with_context(|| format!("Lecture de la scène '{}'", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    parse_scene(&text, base_dir).with_context(|| format!("Scène '{}'", path.display()))
+}
+
+pub fn parse_scene(text: &str, base_dir: &Path) -> Result<Scene> {
+    let mut scene = Scene { world: World::new(), camera: None, settings: RenderSettings::default() };
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        parse_directive(line, base_dir, &mut scene).with_context(|| format!("Ligne {}", number + 1))?;
+    }
+
+    Ok(scene)
+}
+
+fn parse_directive(line: &str, base_dir: &Path, scene: &mut Scene) -> Result<()> {
+    let tokens = tokenize(line)?;
+    let (keyword, fields) = tokens.split_first().ok_or_else(|| anyhow::anyhow!("Ligne vide"))?;
+    let fields = fields
+        .iter()
+        .map(|token| token.split_once('=').ok_or_else(|| anyhow::anyhow!("'{}' invalide, 'clé=valeur' attendu", token)))
+        .collect::<Result<Vec<_>>>()?;
+
+    match keyword.as_str() {
+        "render" => parse_render(&fields, &mut scene.settings),
+        "camera" => {
+            scene.camera = Some(parse_camera(&fields)?);
+            Ok(())
+        }
+        "object" => parse_object(&fields, base_dir, &mut scene.world),
+        other => Err(anyhow::anyhow!("Directive '{}' inconnue (render, camera ou object)", other)),
+    }
+}
+
+fn parse_render(fields: &[(&str, &str)], settings: &mut RenderSettings) -> Result<()> {
+    for &(key, value) in fields {
+        match key {
+            "mode" => settings.mode = value.parse()?,
+            "light" => settings.light.direction = parse_direction(value)?,
+            "ambient" => {
+                settings.light.ambient = parse_f32(value)?;
+                if !(0.0..=1.0).contains(&settings.light.ambient) {
+                    return Err(anyhow::anyhow!("'ambient' doit être compris entre 0 et 1"));
+                }
+            }
+            other => return Err(anyhow::anyhow!("Réglage de rendu '{}' inconnu", other)),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_camera(fields: &[(&str, &str)]) -> Result<Camera> {
+    let mut fovy = std::f32::consts::FRAC_PI_3;
+    let mut pose = Matrix4::identity();
+    let mut far = DEFAULT_FAR;
+    let mut up_axis = None;
+    let mut projection = Projection::Perspective;
+    let mut focus = None;
+
+    for &(key, value) in fields {
+        match key {
+            "fovy" => fovy = parse_f32(value)?,
+            "pose" => pose = parse_affine(value)?,
+            "far" => far = parse_f32(value)?,
+            "up" => up_axis = Some(value.parse::<UpAxis>()?),
+            "projection" => projection = value.parse::<Projection>()?,
+            "focus" => focus = Some(parse_f32(value)?),
+            other => return Err(anyhow::anyhow!("Paramètre de camera '{}' inconnu", other)),
+        }
+    }
+    if far.is_nan() || far <= EPSILON {
+        return Err(anyhow::anyhow!("'far' doit être supérieur à {}", EPSILON));
+    }
+    if let Some(focus) = focus && !(focus.is_finite() && focus > 0.0) {
+        return Err(anyhow::anyhow!("'focus' doit être strictement positif"));
+    }
+    if !(fovy > 0.0 && fovy < PI) {
+        return Err(anyhow::anyhow!("'fovy' doit être compris strictement entre 0 et π"));
+    }
+
+    let mut camera = Camera::new_from_pose(fovy, pose);
+    camera.set_far(far);
+    camera.set_up_axis(up_axis);
+    camera.set_projection(projection);
+    if let Some(focus) = focus {
+        camera.set_focus_distance(focus);
+    }
+    Ok(camera)
+}
+
+fn parse_object(fields: &[(&str, &str)], base_dir: &Path, world: &mut World) -> Result<()> {
+    let mut name = None;
+    let mut source = None;
+    let mut parent = None;
+    let mut color = 0xffffff;
+    let mut visible = true;
+    let mut transform = Matrix4::identity();
+    let mut moves: Vec<(&str, &str)> = Vec::new();
+    let mut field = None;
+    let mut colormap = Colormap::default();
+    let mut range = None;
+
+    for &(key, value) in fields {
+        match key {
+            "name" => name = Some(value.to_string()),
+            "surface" => source = Some(ObjectSource::Surface(value.parse()?)),
+            "implicit" => source = Some(ObjectSource::Implicit(value.parse()?)),
+            "curve" => source = Some(ObjectSource::Curve(value.parse()?)),
+            "geodesic" => source = Some(ObjectSource::Geodesic(value.parse()?)),
+            "cloud" => source = Some(ObjectSource::Cloud(base_dir.join(value).to_string_lossy().into_owned())),
+            "mesh" => source = Some(ObjectSource::Mesh(base_dir.join(value).to_string_lossy().into_owned())),
+            "parent" => parent = Some(world.find(value).ok_or_else(|| anyhow::anyhow!("Parent '{}' inconnu (il doit être déclaré avant)", value))?),
+            "color" => color = parse_color(value)?,
+            "visible" => visible = value.parse().map_err(|_| anyhow::anyhow!("'visible' doit valoir true ou false"))?,
+            "transform" => transform = parse_affine(value)?,
+            "translate" | "rotate_x" | "rotate_y" | "rotate_z" => moves.push((key, value)),
+            "color_by" => field = Some(value.parse()?),
+            "colormap" => colormap = value.parse()?,
+            "range" => range = Some(parse_range(value)?),
+            other => return Err(anyhow::anyhow!("Paramètre d'objet '{}' inconnu", other)),
+        }
+    }
+
+    let source = source.ok_or_else(|| anyhow::anyhow!("Objet sans géométrie, 'surface=', 'implicit=', 'curve=', 'geodesic=', 'cloud=' ou 'mesh=' attendu"))?;
+    let name = name.unwrap_or_else(|| world.unused_name(&format!("objet {}", world.len() + 1)));
+
+    let id = world.add_from_source(name, source, transform, color, parent)?;
+    let node = world.node_mut(id).expect("noeud qui vient d'être ajouté");
+    node.visible = visible;
+
+    for (key, value) in moves {
+        apply_move(node.object_mut(), key, value)?;
+    }
+
+    if let Some(field) = field {
+        world.set_coloring(id, SurfaceColoring::new(field, colormap, range))?;
+    }
+
+    Ok(())
+}
+
+//déplacements relatifs au repère de l'objet, avec les mêmes conventions que les méthodes d'Object
+fn apply_move(object: &mut Object, key: &str, value: &str) -> Result<()> {
+    match key {
+        "translate" => object.translate_relative(parse_vector(value)?),
+        "rotate_x" => object.rotate_x(parse_f32(value)?),
+        "rotate_y" => object.rotate_y(parse_f32(value)?),
+        _ => object.rotate_z(parse_f32(value)?),
+    }
+
+    Ok(())
+}
+
+//découpe en mots séparés par des espaces, les guillemets permettent des valeurs contenant des espaces
+//\" et \\ donnent un guillemet et une barre oblique inverse, toute autre barre oblique inverse est gardée telle quelle (chemins Windows)
+fn tokenize(line: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some('"' | '\\')) => current.extend(chars.next()),
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(anyhow::anyhow!("Guillemet non fermé"));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_f32(value: &str) -> Result<f32> {
+    value.trim().parse().map_err(|_| anyhow::anyhow!("Nombre '{}' invalide", value))
+}
+
+fn parse_floats(value: &str, count: usize) -> Result<Vec<f32>> {
+    let floats = value.split(',').map(parse_f32).collect::<Result<Vec<_>>>()?;
+
+    if floats.len() != count {
+        return Err(anyhow::anyhow!("{} valeurs attendues dans '{}'", count, value));
+    }
+
+    Ok(floats)
+}
+
+fn parse_range(value: &str) -> Result<(f32, f32)> {
+    let v = parse_floats(value, 2)?;
+    Ok((v[0], v[1]))
+}
+
+fn parse_vector(value: &str) -> Result<Vector3<f32>> {
+    let v = parse_floats(value, 3)?;
+    Ok(Vector3::new(v[0], v[1], v[2]))
+}
+
+//direction non nulle, pour la lumière qui est normalisée à l'éclairage
+fn parse_direction(value: &str) -> Result<Vector3<f32>> {
+    let direction = parse_vector(value)?;
+    if !direction.iter().all(|c| c.is_finite()) || direction.norm() <= f32::EPSILON {
+        return Err(anyhow::anyhow!("Direction '{}' invalide, un vecteur non nul est attendu", value));
+    }
+    Ok(direction)
+}
+
+//les trois premières lignes d'une matrice homogène, la dernière étant toujours 0 0 0 1
+fn parse_affine(value: &str) -> Result<Matrix4<f32>> {
+    let m = parse_floats(value, 12)?;
+    Ok(Matrix4::new(
+        m[0], m[1], m[2], m[3],
+        m[4], m[5], m[6], m[7],
+        m[8], m[9], m[10], m[11],
+        0.0, 0.0, 0.0, 1.0,
+    ))
+}
+
+fn parse_color(value: &str) -> Result<u32> {
+    let hex = value.trim_start_matches('#');
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 6)
+        .ok_or_else(|| anyhow::anyhow!("Couleur '{}' invalide, '#rrggbb' attendu", value))
+}
+
+fn format_affine(m: &Matrix4<f32>) -> String {
+    (0..3).flat_map(|i| (0..4).map(move |j| m[(i, j)].to_string())).collect::<Vec<_>>().join(",")
+}
+
+fn format_vector(v: &Vector3<f32>) -> String {
+    format!("{},{},{}", v.x, v.y, v.z)
+}
+
+//valeur entre guillemets, relue telle quelle par tokenize
+fn quoted(value: impl fmt::Display) -> String {
+    format!("\"{}\"", value.to_string().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+//les chemins chargés sont relatifs au dossier courant : ils sont rendus absolus, puis relatifs au dossier de la scène s'ils y sont, absolus sinon
+fn relative_to(path: &str, base_dir: &Path) -> String {
+    let absolute = |path: &Path| path.canonicalize().or_else(|_| std::path::absolute(path)).unwrap_or_else(|_| path.to_path_buf());
+    let base_dir = if base_dir.as_os_str().is_empty() { Path::new(".") } else { base_dir };
+    let (path, base_dir) = (absolute(Path::new(path)), absolute(base_dir));
+    path.strip_prefix(&base_dir).unwrap_or(&path).display().to_string()
+}
+
+//les chemins des nuages et des maillages sont écrits relativement au dossier du fichier si possible
+pub fn scene_to_string(world: &World, camera: &Camera, settings: &RenderSettings, base_dir: &Path) -> String {
+    let mut text = String::from("# scène manifolds-visualizer\n");
+    let DirectionalLight { direction, ambient } = settings.light;
+
+    let _ = writeln!(text, "render mode={} light={} ambient={}", settings.mode, format_vector(&direction), ambient);
+    let up = camera.up_axis().map(|axis| format!(" up={axis}")).unwrap_or_default();
+    let projection = match camera.projection() {
+        Projection::Perspective => String::new(),
+        Projection::Orthographic => format!(" projection={} focus={}", camera.projection(), camera.focus_distance()),
+    };
+    let _ = writeln!(text, "camera fovy={} far={}{}{} pose={}", camera.fovy(), camera.far(), up, projection, format_affine(&camera.cam_to_world()));
+
+    for (_, node) in world.nodes() {
+        let source = match &node.source {
+            Some(ObjectSource::Surface(spec)) => format!("surface={}", quoted(spec)),
+            Some(ObjectSource::Implicit(spec)) => format!("implicit={}", quoted(spec)),
+            Some(ObjectSource::Curve(spec)) => format!("curve={}", quoted(spec)),
+            Some(ObjectSource::Geodesic(spec)) => format!("geodesic={}", quoted(spec)),
+            Some(ObjectSource::Cloud(path)) => format!("cloud={}", quoted(relative_to(path, base_dir))),
+            Some(ObjectSource::Mesh(path)) => format!("mesh={}", quoted(relative_to(path, base_dir))),
+            None => {
+                let _ = writeln!(text, "# '{}' n'a pas de source et n'est pas sauvegardé", node.name);
+                continue;
+            }
+        };
+
+        let _ = write!(text, "object name={} {} color=#{:06x} transform={}", quoted(&node.name), source, node.object().color(), format_affine(&node.object().local_to_world));
+
+        if let Some(parent) = node.parent().and_then(|parent| world.node(parent)) {
+            let _ = write!(text, " parent={}", quoted(&parent.name));
+        }
+        if let Some(coloring) = &node.coloring {
+            let _ = write!(text, " color_by={} colormap={}", quoted(&coloring.field), coloring.colormap);
+            if let Some((min, max)) = coloring.range {
+                let _ = write!(text, " range={min},{max}");
+            }
+        }
+        if !node.visible {
+            text.push_str(" visible=false");
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+pub fn save_scene(path: &Path, world: &World, camera: &Camera, settings: &RenderSettings) -> Result<()> {
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    fs::write(path, scene_to_string(world, camera, settings, base_dir))
+        .with_context(|| format!("Ecriture de la scène '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"
+render mode=gouraud light=-1,-1,-1 ambient=0.15
+camera fovy=1.047 far=10000 up=z projection=ortho focus=100 pose=-1,0,0,0,0,0,1,-100,0,1,0,0
+object name="tore" surface=torus:r=30,r_rev=10 color=#ff8800
+object name="lune" parent="tore" surface=sphere:r=3 translate=40,0,0
+object name="hélicoïde" surface="expr:x=v cos(u); y=v sin(u); z=2u; u_range=0..4pi; v_range=-5..5" visible=false
+object name="gyroïde" implicit="gyroid; scale=3; n=10"
+object name="noeud" curve=torus_knot:p=2,q=3 color=#ffcc00
+object name="géodésiques" geodesic="u=0,v=1,fan=3,length=20|torus:r=30,r_rev=10" color=#ffff00
+object name="selle \"à cheval\"" surface="expr:x=u; y=v; z=u v/5; u_range=-5..5; v_range=-5..5" color_by=gaussian colormap=diverging range=-0.05,0.05
+"#;
+
+    fn round_trip(text: &str) -> String {
+        let scene = parse_scene(text, Path::new("")).unwrap();
+        scene_to_string(&scene.world, scene.camera.as_ref().unwrap(), &scene.settings, Path::new(""))
+    }
+
+    fn pose(text: &str) -> Matrix4<f32> {
+        parse_scene(text, Path::new("")).unwrap().camera.unwrap().cam_to_world()
+    }
+
+    //la pose peut être arrondie à la première sauvegarde, mais ne doit plus bouger ensuite
+    #[test]
+    fn save_then_load_is_stable() {
+        let first = round_trip(SCENE);
+        let saved = round_trip(&first);
+        assert_eq!(round_trip(&saved), saved);
+        assert!((pose(SCENE) - pose(&saved)).abs().max() < 1e-5);
+
+        let scene = parse_scene(&saved, Path::new("")).unwrap();
+        assert_eq!(scene.world.len(), 7);
+
+        let moon = scene.world.find("lune").unwrap();
+        assert_eq!(scene.world.node(moon).unwrap().parent(), scene.world.find("tore"));
+        assert!(!scene.world.node(scene.world.find("hélicoïde").unwrap()).unwrap().visible);
+
+        let saddle = scene.world.node(scene.world.find("selle \"à cheval\"").unwrap()).unwrap();
+        assert_eq!(saddle.coloring.as_ref().unwrap().range, Some((-0.05, 0.05)));
+    }
+
+    #[test]
+    fn tokenize_quotes_and_escapes() {
+        assert_eq!(tokenize(r#"object name="a b" path="C:\dir\x \"y\" \\""#).unwrap(), ["object", "name=a b", r#"path=C:\dir\x "y" \"#]);
+        assert!(tokenize(r#"object name="a"#).is_err());
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        for text in [
+            "render light=0,0,0",
+            "render ambient=1.5",
+            "camera fovy=0",
+            "camera fovy=4",
+            "camera far=0",
+            "camera far=nan",
+            "camera focus=0",
+            "camera focus=-5",
+            "object name=a surface=sphere:r=3\nobject name=a surface=sphere:r=4",
+            "object name=b parent=a surface=sphere:r=3",
+            "object name=a",
+        ] {
+            assert!(parse_scene(text, Path::new("")).is_err(), "'{text}' accepté");
+        }
+    }
+}
@@ -1,3 +1,198 @@
+use std::sync::OnceLock;
+
+use nalgebra::Matrix4;
+
+use crate::{drawing::{colormap::Legend, shading::RenderMode}, objects::{Object, ObjectSource, Primitive3f, bounds::BoundingSphere, coloring::SurfaceColoring}};
+
+pub type NodeId = usize;
+
+//un objet de la scène, sa transformation local_to_world est exprimée dans le repère de son parent s'il en a un
+pub struct Node {
+    pub name: String,
+    object: Object, //privé : toute modification passe par object_mut, qui oublie les primitives gardées
+    pub visible: bool,
+    pub source: Option<ObjectSource>, //absente pour les objets construits dans le code, qui ne peuvent pas être sauvegardés
+    pub coloring: Option<SurfaceColoring>,
+    parent: Option<NodeId>,
+    bounds: Option<BoundingSphere>, //sphère englobante de la géométrie dans le repère de l'objet, calculée une fois pour le culling
+    primitives: OnceLock<Vec<Primitive3f>>, //géométrie générée pour le fil de fer, dans le repère de l'objet
+    triangles: OnceLock<Vec<Primitive3f>>, //de même pour les rendus ombrés
+}
+
+impl Node {
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn object(&self) -> &Object {
+        &self.object
+    }
+
+    //accès en écriture à l'objet, sa géométrie pouvant changer les primitives gardées sont regénérées au rendu suivant
+    pub fn object_mut(&mut self) -> &mut Object {
+        self.invalidate_primitives();
+        &mut self.object
+    }
+
+    //primitives à dessiner selon le mode, dans le repère de l'objet et avant éclairage
+    //elles sont générées au premier rendu (évaluation des surfaces, des courbes, des normales) puis gardées jusqu'à une modification de l'objet (object_mut, set_coloring)
+    //les nuages de points ne passent pas par là, ils sont lus directement pour ne pas être recopiés
+    pub fn raw_primitives(&self, mode: RenderMode) -> &[Primitive3f] {
+        match mode {
+            RenderMode::Wireframe => self.primitives.get_or_init(|| self.object.raw_primitives().collect()),
+            RenderMode::Flat | RenderMode::Gouraud => self.triangles.get_or_init(|| self.object.raw_triangles().collect()),
+        }
+    }
+
+    fn invalidate_primitives(&mut self) {
+        self.primitives.take();
+        self.triangles.take();
+    }
+}
+
+//graphe de scène : les noeuds sont stockés à plat, un parent est toujours ajouté avant ses enfants
+#[derive(Default)]
 pub struct World {
-    
-}
\ No newline at end of file
+    nodes: Vec<Node>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //les noms sont uniques : c'est par eux que find et les fichiers de scène désignent un noeud
+    pub fn add(&mut self, name: impl Into<String>, object: Object) -> anyhow::Result<NodeId> {
+        self.insert(name.into(), object, None)
+    }
+
+    pub fn add_child(&mut self, parent: NodeId, name: impl Into<String>, object: Object) -> anyhow::Result<NodeId> {
+        self.insert(name.into(), object, Some(parent))
+    }
+
+    //un nom déjà pris ou un parent inexistant sont refusés
+    fn check_new_node(&self, name: &str, parent: Option<NodeId>) -> anyhow::Result<()> {
+        if self.find(name).is_some() {
+            return Err(anyhow::anyhow!("Il y a déjà un objet nommé '{}'", name));
+        }
+        if let Some(parent) = parent && parent >= self.nodes.len() {
+            return Err(anyhow::anyhow!("Parent {} inexistant", parent));
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, name: String, object: Object, parent: Option<NodeId>) -> anyhow::Result<NodeId> {
+        self.check_new_node(&name, parent)?;
+        let bounds = object.geometry_bounding_sphere();
+        self.nodes.push(Node { name, object, visible: true, source: None, coloring: None, parent, bounds, primitives: OnceLock::new(), triangles: OnceLock::new() });
+        Ok(self.nodes.len() - 1)
+    }
+
+    //ajoute un objet dont la géométrie est chargée depuis sa source, le nom et le parent sont vérifiés avant le chargement
+    pub fn add_from_source(&mut self, name: impl Into<String>, source: ObjectSource, local_to_world: Matrix4<f32>, color: u32, parent: Option<NodeId>) -> anyhow::Result<NodeId> {
+        let name = name.into();
+        self.check_new_node(&name, parent)?;
+
+        let object = Object::new(source.load()?, local_to_world, color);
+        let id = self.insert(name, object, parent)?;
+        self.nodes[id].source = Some(source);
+
+        Ok(id)
+    }
+
+    //colore une surface par un champ scalaire, la coloration est gardée pour la sauvegarde de la scène
+    pub fn set_coloring(&mut self, id: NodeId, coloring: SurfaceColoring) -> anyhow::Result<()> {
+        let node = self.nodes.get_mut(id).ok_or_else(|| anyhow::anyhow!("Objet {} inexistant", id))?;
+        node.object.set_coloring(&coloring).map_err(|e| anyhow::anyhow!("'{}' : {}", node.name, e))?;
+        node.coloring = Some(coloring);
+        node.invalidate_primitives();
+
+        Ok(())
+    }
+
+    //légende du premier objet visible coloré par un champ
+    pub fn legend(&self) -> Option<&Legend> {
+        self.nodes().filter(|&(id, _)| self.is_visible(id)).find_map(|(_, node)| node.object.legend())
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().enumerate()
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id)
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    //name s'il est libre, sinon name suivi du premier numéro " (2)", " (3)"... qui l'est
+    pub fn unused_name(&self, name: &str) -> String {
+        std::iter::once(name.to_string())
+            .chain((2..).map(|i| format!("{name} ({i})")))
+            .find(|candidate| self.find(candidate).is_none())
+            .expect("suite infinie de noms")
+    }
+
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes().filter(move |(_, node)| node.parent == Some(id)).map(|(child, _)| child)
+    }
+
+    pub fn toggle_visibility(&mut self, id: NodeId) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.visible = !node.visible;
+        }
+    }
+
+    //un noeud masqué masque aussi tous ses descendants
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        let node = &self.nodes[id];
+        node.visible && node.parent.is_none_or(|parent| self.is_visible(parent))
+    }
+
+    //transformation du repère du parent vers le monde (composition des local_to_world des ancêtres)
+    pub fn parent_to_world(&self, id: NodeId) -> Matrix4<f32> {
+        match self.nodes[id].parent {
+            Some(parent) => self.local_to_world(parent),
+            None => Matrix4::identity(),
+        }
+    }
+
+    //transformation complète du repère de l'objet vers le monde
+    pub fn local_to_world(&self, id: NodeId) -> Matrix4<f32> {
+        self.parent_to_world(id) * self.nodes[id].object.local_to_world
+    }
+
+    //sphère englobante d'un objet dans le repère du monde, sans ses enfants
+    pub fn bounding_sphere(&self, id: NodeId) -> Option<BoundingSphere> {
+        self.nodes[id].bounds.map(|sphere| sphere.transformed(&self.local_to_world(id)))
+    }
+
+    //sphère englobant tous les objets visibles, None si aucun n'a de géométrie
+    pub fn visible_bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.nodes()
+            .filter(|&(id, _)| self.is_visible(id))
+            .filter_map(|(id, _)| self.bounding_sphere(id))
+            .reduce(|a, b| a.merged(&b))
+    }
+
+    //noeuds visibles ayant une géométrie dont la sphère englobante (dans le monde) passe le test, typiquement l'intersection avec le champ de la camera
+    pub fn visible_nodes_where<'a>(&'a self, keep: impl Fn(&BoundingSphere) -> bool + 'a) -> impl Iterator<Item = (NodeId, &'a Node)> + 'a {
+        self.nodes()
+            .filter(|&(id, _)| self.is_visible(id))
+            .filter(move |&(id, _)| self.bounding_sphere(id).is_some_and(|sphere| keep(&sphere)))
+    }
+}